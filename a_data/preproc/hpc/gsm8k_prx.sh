@@ -42,6 +42,7 @@ for TYPE in language context; do
   echo "Processing GSM8K ($TYPE)..."
 
   if ! cargo gen_phrx_skipfail \
+      --dataset gsm8k \
       --version-set "$TYPE" \
       --model "gemini-2.5-flash-preview-05-20" \
       --max-attempts 12 \
@@ -41,6 +41,7 @@ for TYPE in syntax; do
   echo "Processing mmlu ($TYPE)..."
 
   if ! cargo gen_phrx_skipfail \
+      --dataset mmlu \
       --version-set "$TYPE" \
       --model "gemini-2.5-flash-preview-05-20" \
       --max-attempts 12 \
@@ -0,0 +1,255 @@
+// One importer per source dataset, all mapping into `Record`.
+//
+// Inputs are local files as the download scripts or the original releases
+// ship them: a JSON array or JSONL (sniffed), or for MMLU also the header-less
+// `question,A,B,C,D,answer` CSVs. Rows an importer cannot map are skipped with
+// a reason instead of failing the whole file.
+
+use crate::record::{clean, Record};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+// Split names recognised in file names, longest first so `auxiliary_train`
+// wins over `train`
+pub const SPLITS: [&str; 6] = ["auxiliary_train", "validation", "train", "test", "val", "dev"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dataset {
+    Alpaca,
+    Gsm8k,
+    Mmlu,
+}
+
+impl Dataset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Alpaca => "alpaca",
+            Self::Gsm8k => "gsm8k",
+            Self::Mmlu => "mmlu",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imported {
+    // in file order, `prompt_count` still 0 and `prompt_id` empty unless the
+    // source had one
+    pub records: Vec<Record>,
+    // `<path>:<row>: <reason>`
+    pub skipped: Vec<String>,
+}
+
+pub fn import(dataset: Dataset, path: &Path) -> Result<Imported> {
+    let (split, subject) = split_and_subject(path);
+    let mut out = Imported::default();
+
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+        if dataset != Dataset::Mmlu {
+            bail!("{}: CSV input is only supported for mmlu", path.display());
+        }
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        for (i, row) in reader.records().enumerate() {
+            let row = row.with_context(|| format!("{}:{}: malformed CSV row", path.display(), i + 1))?;
+            let cells: Vec<&str> = row.iter().collect();
+            // tolerate a header line some re-exports add
+            if i == 0 && cells.last().is_some_and(|c| c.trim().eq_ignore_ascii_case("answer")) {
+                continue;
+            }
+            match mmlu_csv_row(&cells, &split, &subject) {
+                Ok(rec) => out.records.push(rec),
+                Err(reason) => out.skipped.push(format!("{}:{}: {reason}", path.display(), i + 1)),
+            }
+        }
+        return Ok(out);
+    }
+
+    for (i, value) in read_values(path)?.into_iter().enumerate() {
+        let Value::Object(obj) = value else {
+            out.skipped.push(format!("{}:{}: not a JSON object", path.display(), i + 1));
+            continue;
+        };
+        let mapped = match dataset {
+            Dataset::Alpaca => alpaca(&obj, &split),
+            Dataset::Gsm8k => gsm8k(&obj, &split),
+            Dataset::Mmlu => mmlu_json(&obj, &split, &subject),
+        };
+        match mapped {
+            Ok(rec) => out.records.push(rec),
+            Err(reason) => out.skipped.push(format!("{}:{}: {reason}", path.display(), i + 1)),
+        }
+    }
+    Ok(out)
+}
+
+// A JSON array, or one object per line
+fn read_values(path: &Path) -> Result<Vec<Value>> {
+    let raw = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if raw.trim_start().starts_with('[') {
+        return serde_json::from_str(&raw).with_context(|| format!("{} is not a JSON array", path.display()));
+    }
+    raw.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("{}:{}: invalid JSON", path.display(), i + 1)))
+        .collect()
+}
+
+// `abstract_algebra_test.csv` -> (test, abstract_algebra), `train.jsonl` ->
+// (train, train); no recognised split means `train`
+fn split_and_subject(path: &Path) -> (String, String) {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    for split in SPLITS {
+        if stem == split {
+            return (split.to_string(), stem);
+        }
+        if let Some(subject) = stem.strip_suffix(&format!("_{split}")) {
+            return (split.to_string(), subject.to_string());
+        }
+    }
+    ("train".to_string(), stem)
+}
+
+fn text(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|k| obj.get(*k).and_then(Value::as_str))
+        .map(clean)
+}
+
+fn instruction(obj: &Map<String, Value>, keys: &[&str]) -> Result<String, String> {
+    text(obj, keys)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| format!("no {}", keys.join(" / ")))
+}
+
+fn record_split(obj: &Map<String, Value>, from_path: &str) -> String {
+    text(obj, &["split"]).filter(|s| !s.is_empty()).unwrap_or_else(|| from_path.to_string())
+}
+
+fn source_id(obj: &Map<String, Value>) -> String {
+    text(obj, &["prompt_id"]).unwrap_or_default()
+}
+
+fn alpaca(obj: &Map<String, Value>, split: &str) -> Result<Record, String> {
+    Ok(Record {
+        prompt_count: 0,
+        prompt_id: source_id(obj),
+        instruction_original: instruction(obj, &["instruction", "instruction_original"])?,
+        input: text(obj, &["input"]).unwrap_or_default(),
+        output: text(obj, &["output"]),
+        answer: None,
+        choices: None,
+        subject: None,
+        split: record_split(obj, split),
+    })
+}
+
+// GSM8K `answer` is the worked solution ending in `#### <number>`; the
+// solution becomes `output`, the number `answer`
+fn gsm8k(obj: &Map<String, Value>, split: &str) -> Result<Record, String> {
+    let solution = text(obj, &["answer", "output"]);
+    let (output, answer) = match solution.as_deref().and_then(|s| s.rsplit_once("####")) {
+        Some((_, number)) => (solution.clone(), Some(Value::String(number.trim().replace(',', "")))),
+        // already converted: keep whatever answer there is
+        None => (text(obj, &["output"]), obj.get("answer").cloned()),
+    };
+    if answer.is_none() {
+        return Err("no answer".to_string());
+    }
+    Ok(Record {
+        prompt_count: 0,
+        prompt_id: source_id(obj),
+        instruction_original: instruction(obj, &["question", "instruction_original", "instruction"])?,
+        input: String::new(),
+        output,
+        answer,
+        choices: None,
+        subject: None,
+        split: record_split(obj, split),
+    })
+}
+
+fn mmlu_json(obj: &Map<String, Value>, split: &str, subject: &str) -> Result<Record, String> {
+    let choices: Vec<String> = obj
+        .get("choices")
+        .and_then(Value::as_array)
+        .ok_or("no choices")?
+        .iter()
+        .map(|c| c.as_str().map(clean).ok_or("a choice is not a string"))
+        .collect::<Result<_, _>>()?;
+    let answer = match obj.get("answer") {
+        Some(Value::Number(n)) => n.as_u64().ok_or("answer is not a choice index")? as usize,
+        Some(Value::String(s)) => answer_index(s)?,
+        _ => return Err("no answer".to_string()),
+    };
+    mmlu_record(
+        instruction(obj, &["question", "instruction_original", "instruction"])?,
+        choices,
+        answer,
+        text(obj, &["subject"]).filter(|s| !s.is_empty()).unwrap_or_else(|| subject.to_string()),
+        record_split(obj, split),
+        source_id(obj),
+    )
+}
+
+fn mmlu_csv_row(cells: &[&str], split: &str, subject: &str) -> Result<Record, String> {
+    let [question, choices @ .., answer] = cells else {
+        return Err(format!("{} columns, expected question, choices…, answer", cells.len()));
+    };
+    if choices.len() < 2 {
+        return Err(format!("{} columns, expected question, choices…, answer", cells.len()));
+    }
+    mmlu_record(
+        clean(question),
+        choices.iter().map(|c| clean(c)).collect(),
+        answer_index(answer)?,
+        subject.to_string(),
+        split.to_string(),
+        String::new(),
+    )
+}
+
+fn mmlu_record(
+    question: String,
+    choices: Vec<String>,
+    answer: usize,
+    subject: String,
+    split: String,
+    prompt_id: String,
+) -> Result<Record, String> {
+    if question.is_empty() {
+        return Err("empty question".to_string());
+    }
+    if answer >= choices.len() {
+        return Err(format!("answer {answer} out of range for {} choices", choices.len()));
+    }
+    Ok(Record {
+        prompt_count: 0,
+        prompt_id,
+        instruction_original: question,
+        input: String::new(),
+        output: None,
+        answer: Some(Value::from(answer)),
+        choices: Some(choices),
+        subject: Some(subject),
+        split,
+    })
+}
+
+// `B` / `b` / `1` -> 1
+fn answer_index(cell: &str) -> Result<usize, String> {
+    let cell = cell.trim();
+    if let Ok(i) = cell.parse::<usize>() {
+        return Ok(i);
+    }
+    match cell.as_bytes() {
+        [c] if c.is_ascii_alphabetic() => Ok((c.to_ascii_uppercase() - b'A') as usize),
+        _ => Err(format!("answer {cell:?} is neither a letter nor an index")),
+    }
+}
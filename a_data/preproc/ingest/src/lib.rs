@@ -0,0 +1,4 @@
+pub mod import;
+pub mod manifest;
+pub mod record;
+pub mod sample;
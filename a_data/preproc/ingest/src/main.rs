@@ -0,0 +1,184 @@
+/*
+# Alpaca JSONL from download_alpaca.py
+cargo ingest --dataset alpaca \
+    a_data/alpaca/alpaca_52k_clean.jsonl \
+    -o a_data/alpaca/alpaca_52k.json
+
+# GSM8K, both splits into one file (split taken from the file name)
+cargo ingest --dataset gsm8k \
+    a_data/gsm8k/train.jsonl a_data/gsm8k/test.jsonl \
+    -o a_data/gsm8k/main.json
+
+# MMLU: original CSV release or download_mmlu.py JSON, subject from the file name
+cargo ingest --dataset mmlu \
+    data/test/moral_scenarios_test.csv data/test/world_religions_test.csv \
+    -o a_data/mmlu/moral_religions.jsonl --start-id 401
+
+# keep the UUIDs of an existing slice that paraphrases were already keyed on
+cargo ingest --dataset alpaca --keep-source-ids \
+    a_data/alpaca/alpaca_5k_proc.jsonl -o a_data/alpaca/alpaca_5k.json
+*/
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use clap::Parser;
+use ingest::{
+    import::{import, Dataset},
+    manifest::{manifest_path, sha256_file, Input, Manifest},
+    record::{stable_id, Record},
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+// Import Alpaca / GSM8K / MMLU files into the canonical record schema,
+// number them and write a manifest next to the output
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    #[arg(long, value_enum)]
+    dataset: Dataset,
+
+    // Source files, imported in the order given
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    // Output file; `.jsonl` writes JSONL, anything else a JSON array
+    #[arg(short, long)]
+    output: PathBuf,
+
+    // Value to start `prompt_count` from
+    #[arg(short = 's', long = "start-id", default_value_t = 1)]
+    start_id: u64,
+
+    // Split for every record, instead of the record's own or the file name's
+    #[arg(long)]
+    split: Option<String>,
+
+    // Keep a `prompt_id` the source already has instead of deriving one
+    #[arg(long)]
+    keep_source_ids: bool,
+
+    // Keep records whose content was already imported
+    #[arg(long)]
+    keep_duplicates: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let dataset = args.dataset.name();
+
+    let mut records: Vec<Record> = Vec::new();
+    let mut inputs: Vec<Input> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut duplicates = 0usize;
+
+    for path in &args.inputs {
+        let imported = import(args.dataset, path)?;
+        let mut kept = 0usize;
+        for mut rec in imported.records {
+            // duplicates are found by content, whichever ID is kept
+            let content_id = stable_id(dataset, &rec);
+            if !args.keep_source_ids || rec.prompt_id.is_empty() {
+                rec.prompt_id = content_id.clone();
+            }
+            if !seen.insert(content_id) && !args.keep_duplicates {
+                duplicates += 1;
+                continue;
+            }
+            if let Some(split) = &args.split {
+                rec.split = split.clone();
+            }
+            rec.prompt_count = args.start_id + records.len() as u64;
+            records.push(rec);
+            kept += 1;
+        }
+        println!(
+            "{:>6} records, {:>4} skipped  {}",
+            kept,
+            imported.skipped.len(),
+            path.display()
+        );
+        inputs.push(Input {
+            path: path.display().to_string(),
+            sha256: sha256_file(path)?,
+            records: kept,
+            skipped: imported.skipped.len(),
+        });
+        skipped.extend(imported.skipped);
+    }
+    if records.is_empty() {
+        bail!("no records imported");
+    }
+
+    let jsonl = args.output.extension().is_some_and(|e| e == "jsonl");
+    let mut text = if jsonl {
+        records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n")
+    } else {
+        serde_json::to_string_pretty(&records)?
+    };
+    text.push('\n');
+    if let Some(dir) = args.output.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&args.output, text).with_context(|| format!("failed to write {}", args.output.display()))?;
+
+    let mut splits: BTreeMap<String, usize> = BTreeMap::new();
+    let mut subjects: BTreeMap<String, usize> = BTreeMap::new();
+    let mut fields: BTreeMap<String, usize> = BTreeMap::new();
+    for rec in &records {
+        *splits.entry(rec.split.clone()).or_default() += 1;
+        if let Some(subject) = &rec.subject {
+            *subjects.entry(subject.clone()).or_default() += 1;
+        }
+        for (field, present) in [
+            ("input", !rec.input.is_empty()),
+            ("output", rec.output.as_deref().is_some_and(|s| !s.is_empty())),
+            ("answer", rec.answer.is_some()),
+            ("choices", rec.choices.is_some()),
+            ("subject", rec.subject.is_some()),
+        ] {
+            *fields.entry(field.to_string()).or_default() += present as usize;
+        }
+    }
+
+    let manifest = Manifest {
+        tool: format!("ingest {}", env!("CARGO_PKG_VERSION")),
+        created: Local::now().to_rfc3339(),
+        dataset: dataset.to_string(),
+        output: args.output.display().to_string(),
+        output_sha256: sha256_file(&args.output)?,
+        records: records.len(),
+        first_prompt_count: records[0].prompt_count,
+        last_prompt_count: records[records.len() - 1].prompt_count,
+        id_scheme: if args.keep_source_ids { "source" } else { "content-v8" }.to_string(),
+        inputs,
+        duplicates,
+        splits,
+        subjects,
+        fields,
+        skipped,
+    };
+    let manifest_file = manifest_path(&args.output);
+    fs::write(&manifest_file, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("failed to write {}", manifest_file.display()))?;
+
+    println!(
+        "{} {dataset} records (prompt_count {}–{}) written to {}, {} duplicates dropped, {} rows skipped",
+        manifest.records,
+        manifest.first_prompt_count,
+        manifest.last_prompt_count,
+        args.output.display(),
+        manifest.duplicates,
+        manifest.skipped.len()
+    );
+    println!("manifest written to {}", manifest_file.display());
+    Ok(())
+}
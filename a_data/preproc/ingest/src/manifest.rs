@@ -0,0 +1,52 @@
+// `<output>.manifest.json` written next to every ingested file: where the
+// records came from (with content hashes, so a changed source is noticed),
+// how IDs were assigned and what ended up in the output.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub tool: String,
+    pub created: String,
+    pub dataset: String,
+    pub output: String,
+    pub output_sha256: String,
+    pub records: usize,
+    pub first_prompt_count: u64,
+    pub last_prompt_count: u64,
+    // `content-v8` (derived by `stable_id`) or `source` (kept from the input where present)
+    pub id_scheme: String,
+    pub inputs: Vec<Input>,
+    pub duplicates: usize,
+    pub splits: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub subjects: BTreeMap<String, usize>,
+    // records with a non-empty value, per optional field
+    pub fields: BTreeMap<String, usize>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Input {
+    pub path: String,
+    pub sha256: String,
+    pub records: usize,
+    pub skipped: usize,
+}
+
+// `a/main.json` -> `a/main.manifest.json`
+pub fn manifest_path(output: &Path) -> PathBuf {
+    output.with_extension("manifest.json")
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(Sha256::digest(&bytes).iter().map(|b| format!("{b:02x}")).collect())
+}
@@ -0,0 +1,59 @@
+// The canonical prompt record every importer maps into.
+//
+// `output` is a reference response (Alpaca `output`, the worked GSM8K
+// solution); `answer` is the gold answer to score against (the GSM8K number
+// after `####`, the MMLU choice index). Fields a dataset does not have are
+// left out of the JSON rather than written as null.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub prompt_count: u64,
+    pub prompt_id: String,
+    #[serde(alias = "instruction")]
+    pub instruction_original: String,
+    #[serde(default)]
+    pub input: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choices: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub split: String,
+}
+
+// CRLF / CR -> LF and surrounding whitespace stripped, as download_alpaca.py does
+pub fn clean(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n").trim().to_string()
+}
+
+// Content-derived ID in UUID layout (version 8, RFC 9562), so re-importing the
+// same prompt from the same dataset always gives the same `prompt_id`. The
+// split is not part of it: one prompt in two splits is a duplicate.
+pub fn stable_id(dataset: &str, rec: &Record) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        dataset,
+        rec.subject.as_deref().unwrap_or(""),
+        &rec.instruction_original,
+        &rec.input,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    for choice in rec.choices.iter().flatten() {
+        hasher.update(choice.as_bytes());
+        hasher.update([0x1f]);
+    }
+    let mut b: [u8; 16] = hasher.finalize()[..16].try_into().expect("sha256 is 32 bytes");
+    b[6] = (b[6] & 0x0f) | 0x80;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let hex: String = b.iter().map(|x| format!("{x:02x}")).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
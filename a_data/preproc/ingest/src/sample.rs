@@ -0,0 +1,128 @@
+// Seeded stratified sampling for the prompt slices (slice_100, slice_500,
+// main_500, …) and the sampling manifest that makes a slice reproducible.
+//
+// Every item gets a stratum key (e.g. `explain|short|no_input`); each
+// stratum receives a share of the sample proportional to its size (largest
+// remainder, so the shares add up exactly) and its items are drawn with a
+// ChaCha8 RNG seeded from `--seed`. Strata are visited in key order, so the
+// same input, key function and seed always select the same items.
+
+use anyhow::{Context, Result};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stratum {
+    pub available: usize,
+    pub selected: usize,
+}
+
+// Indices of `n` items drawn across the strata of `keys` (one key per item),
+// in input order, plus the per-stratum counts
+pub fn stratified(keys: &[String], n: usize, seed: u64) -> (Vec<usize>, BTreeMap<String, Stratum>) {
+    let mut members: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        members.entry(key).or_default().push(i);
+    }
+    let n = n.min(keys.len());
+    let total = keys.len().max(1) as f64;
+
+    // floor of each proportional share, then the leftovers to the largest remainders
+    let mut quotas: Vec<(&str, usize, f64)> = members
+        .iter()
+        .map(|(key, idx)| {
+            let exact = n as f64 * idx.len() as f64 / total;
+            (*key, exact.floor() as usize, exact - exact.floor())
+        })
+        .collect();
+    // the remainders sum to `left`, so that many strata have one below 1 but
+    // above 0, and none of them is full yet
+    let left = n - quotas.iter().map(|q| q.1).sum::<usize>();
+    let mut order: Vec<usize> = (0..quotas.len()).collect();
+    order.sort_by(|&a, &b| quotas[b].2.total_cmp(&quotas[a].2).then(a.cmp(&b)));
+    for i in order.into_iter().take(left) {
+        quotas[i].1 += 1;
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut picked: Vec<usize> = Vec::with_capacity(n);
+    let mut strata: BTreeMap<String, Stratum> = BTreeMap::new();
+    for (key, quota, _) in quotas {
+        let mut idx = members[key].clone();
+        idx.shuffle(&mut rng);
+        idx.truncate(quota);
+        strata.insert(
+            key.to_string(),
+            Stratum { available: members[key].len(), selected: idx.len() },
+        );
+        picked.extend(idx);
+    }
+    picked.sort_unstable();
+    (picked, strata)
+}
+
+// Word-count bucket of a prompt: short (≤ 12), medium (≤ 30), long
+pub fn length_bucket(text: &str) -> &'static str {
+    match text.split_whitespace().count() {
+        0..=12 => "short",
+        13..=30 => "medium",
+        _ => "long",
+    }
+}
+
+// One sampling run; a slice built by several appends gets one run per append
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SamplingRun {
+    pub tool: String,
+    pub created: String,
+    pub input: String,
+    pub input_sha256: String,
+    pub output: String,
+    pub seed: u64,
+    // features the stratum key is made of, in key order
+    pub strata_by: Vec<String>,
+    pub requested: usize,
+    pub selected: usize,
+    pub strata: BTreeMap<String, Stratum>,
+    // `prompt_count` of each selected item in the input, in output order
+    pub source_prompt_counts: Vec<Value>,
+    // first `prompt_count` in the output, for tools that renumber
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_id: Option<usize>,
+}
+
+pub fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+// `a/main_500.json` -> `a/main_500.sampling.json`
+pub fn sampling_manifest_path(output: &Path) -> PathBuf {
+    output.with_extension("sampling.json")
+}
+
+// Add a run to the manifest (a JSON array), creating it if needed
+pub fn append_run(path: &Path, run: SamplingRun) -> Result<()> {
+    let mut runs: Vec<SamplingRun> = match fs::read_to_string(path) {
+        Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw)
+            .with_context(|| format!("{} is not a sampling manifest", path.display()))?,
+        _ => Vec::new(),
+    };
+    runs.push(run);
+    fs::write(path, serde_json::to_string_pretty(&runs)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+// The strata table as aligned text
+pub fn print_strata(strata: &BTreeMap<String, Stratum>) {
+    println!("{:40} {:>9} {:>8}", "stratum", "available", "selected");
+    for (key, s) in strata {
+        println!("{key:40} {:>9} {:>8}", s.available, s.selected);
+    }
+}
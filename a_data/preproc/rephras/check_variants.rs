@@ -0,0 +1,89 @@
+/*
+cargo check_variants \
+    --version-set obstruction \
+    a_data/alpaca/slice_100/alpaca_prx_obstruction1_slice1.json
+
+# every registered key present in the file, whatever set it came from
+cargo check_variants --all-keys a_data/alpaca/prxed/all.json
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rephras::checkpoint::validation_path;
+use rephras::taxonomy::{Taxonomy, DEFAULT_TAXONOMY};
+use rephras::validate::{registered_keys, Report};
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    prompt_count: u32,
+    #[serde(alias = "instruction", alias = "instruction_original")]
+    instruction_original: String,
+
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+// Validate already generated paraphrases and write `<input>.validation.json`
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    input: PathBuf,
+
+    #[arg(long, default_value = "style", conflicts_with = "all_keys")]
+    version_set: String,
+
+    // Check every key that has a validator instead of one set
+    #[arg(long)]
+    all_keys: bool,
+
+    #[arg(long, default_value = DEFAULT_TAXONOMY)]
+    taxonomy: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let data = fs::read_to_string(&cli.input)
+        .with_context(|| format!("failed to read {}", cli.input.display()))?;
+    let records: Vec<Record> = serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse {}", cli.input.display()))?;
+
+    let mut report;
+    if cli.all_keys {
+        report = Report::new("all");
+        for rec in &records {
+            let keys: Vec<String> = registered_keys()
+                .filter(|k| rec.extra.contains_key(*k))
+                .map(String::from)
+                .collect();
+            report.add(rec.prompt_count, &rec.instruction_original, &rec.extra, &keys);
+        }
+    } else {
+        let taxonomy = Taxonomy::load(&cli.taxonomy)?;
+        let (name, set) = taxonomy.resolve_set(&cli.version_set)?;
+        report = Report::new(name);
+        for rec in &records {
+            report.add(rec.prompt_count, &rec.instruction_original, &rec.extra, &set.keys);
+        }
+    }
+
+    let report_file = validation_path(&cli.input);
+    fs::write(&report_file, serde_json::to_string_pretty(&report)?)
+        .with_context(|| format!("failed to write {}", report_file.display()))?;
+
+    for (key, stats) in report.keys.iter().filter(|(_, s)| s.failed > 0) {
+        println!("{key:40} {:4} / {:4} failed", stats.failed, stats.checked);
+    }
+    if !report.is_clean() {
+        bail!(
+            "{} of {} records have invalid variants – see {}",
+            report.records_failed,
+            report.records,
+            report_file.display()
+        );
+    }
+    println!("all {} records valid", report.records);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+/*
+cargo export_review \
+    --per-set 25 \
+    a_data/alpaca/prxed/all.json \
+    a_data/alpaca/prxed/review_all.tsv
+
+# only two sets, as CSV for a spreadsheet, another sample
+cargo export_review --sets style,obstruction --seed 7 \
+    a_data/alpaca/prxed/all.json /tmp/review_style_obstruction.csv
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rephras::review::{delimiter, set_of, Row};
+use rephras::taxonomy::{Taxonomy, DEFAULT_TAXONOMY};
+use rephras::validate::check_value;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    prompt_count: u32,
+    #[serde(alias = "instruction", alias = "instruction_original")]
+    instruction_original: String,
+
+    #[serde(flatten)]
+    extra: serde_json::Map<String, Value>,
+}
+
+// Sample paraphrases into a review sheet (TSV, or CSV for a `.csv` output)
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    input: PathBuf,
+    output: PathBuf,
+
+    // Rows sampled per set (all rows of smaller sets)
+    #[arg(long, default_value_t = 20)]
+    per_set: usize,
+
+    // Only these sets (default: every set found in the input)
+    #[arg(long, value_delimiter = ',')]
+    sets: Vec<String>,
+
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    #[arg(long, default_value = DEFAULT_TAXONOMY)]
+    taxonomy: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let taxonomy = Taxonomy::load(&cli.taxonomy)?;
+
+    let data = fs::read_to_string(&cli.input)
+        .with_context(|| format!("failed to read {}", cli.input.display()))?;
+    let records: Vec<Record> = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a paraphrase JSON array", cli.input.display()))?;
+
+    // every paraphrase in the file, grouped by set
+    let mut strata: BTreeMap<String, Vec<Row>> = BTreeMap::new();
+    for rec in &records {
+        for (key, value) in &rec.extra {
+            let Some(text) = value.as_str() else { continue };
+            if !key.starts_with("instruct_") {
+                continue;
+            }
+            let set = set_of(&taxonomy, key);
+            if !cli.sets.is_empty() && !cli.sets.contains(&set) {
+                continue;
+            }
+            let auto_check = match check_value(key, &rec.instruction_original, Some(value), true) {
+                Ok(()) => "ok".to_string(),
+                Err(reason) => reason,
+            };
+            strata.entry(set.clone()).or_default().push(Row {
+                prompt_count: rec.prompt_count,
+                set,
+                key: key.clone(),
+                original: rec.instruction_original.clone(),
+                paraphrase: text.to_string(),
+                auto_check,
+                verdict: String::new(),
+                corrected: String::new(),
+                notes: String::new(),
+            });
+        }
+    }
+    if strata.is_empty() {
+        bail!("no paraphrases to review in {}", cli.input.display());
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(cli.seed);
+    let mut sheet: Vec<Row> = Vec::new();
+    for (set, rows) in &mut strata {
+        rows.shuffle(&mut rng);
+        rows.truncate(cli.per_set);
+        rows.sort_by(|a, b| (a.prompt_count, &a.key).cmp(&(b.prompt_count, &b.key)));
+        println!("{set:24} {:>5} rows", rows.len());
+        sheet.append(rows);
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter(&cli.output))
+        .from_path(&cli.output)
+        .with_context(|| format!("failed to create {}", cli.output.display()))?;
+    for row in &sheet {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+
+    println!(
+        "{} rows from {} records written to {} – fill in verdict (ok | fix | reject), corrected, notes",
+        sheet.len(),
+        records.len(),
+        cli.output.display()
+    );
+    Ok(())
+}
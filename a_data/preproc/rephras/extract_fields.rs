@@ -0,0 +1,147 @@
+/*
+cargo extract_fields \
+    --rules a_data/extract_rules/mmlu.toml \
+    --report a_data/mmlu/prxed_moral_500_fields/voice.report.json \
+    a_data/mmlu/prxed_moral_500/voice.json \
+    -o a_data/mmlu/prxed_moral_500_fields/voice.json
+
+# JSONL works too; without input / -o it is a stdin -> stdout filter
+cat data/custom.jsonl | cargo extract_fields --rules my_rules.toml > /tmp/custom_fields.jsonl
+
+# the former add_scenarios pass (`Scenario 1 …` tail -> `scenarios`)
+cargo add_scenarios < \
+    a_data/mmlu/prxed_moral_500/voice.json > \
+    a_data/mmlu/prxed_moral_500_scenarios/voice.json
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rephras::extract::Extractor;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+// Write regex captures of record fields as new fields
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    // JSON array or JSONL file (default / `-`: stdin)
+    input: Option<PathBuf>,
+
+    // Output file (default: stdout); `.jsonl` writes JSONL, `.json` an array,
+    // otherwise the input format is kept
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    // Rules file, see a_data/extract_rules/
+    #[arg(long)]
+    rules: PathBuf,
+
+    // Also write the match report as JSON
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    records: usize,
+    // rule name -> records it matched
+    matches: BTreeMap<String, usize>,
+    // prompt_count (or array index) of records no rule matched
+    unmatched: Vec<Value>,
+}
+
+fn is_jsonl(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "jsonl")
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let extractor = Extractor::load(&cli.rules)?;
+
+    let (raw, from) = match cli.input.as_deref().filter(|p| *p != Path::new("-")) {
+        Some(path) => (
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?,
+            path.display().to_string(),
+        ),
+        None => {
+            let mut raw = String::new();
+            io::stdin().read_to_string(&mut raw)?;
+            (raw, "stdin".to_string())
+        }
+    };
+    // a file named .jsonl, or anything that is not a JSON array
+    let jsonl_in = cli.input.as_deref().map_or(!raw.trim_start().starts_with('['), is_jsonl);
+
+    let values: Vec<Value> = if jsonl_in {
+        raw.lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("{from}:{}: invalid JSON", i + 1)))
+            .collect::<Result<_>>()?
+    } else {
+        serde_json::from_str(&raw).with_context(|| format!("{from} is not a JSON array"))?
+    };
+
+    let mut report = Report { records: values.len(), ..Report::default() };
+    for name in extractor.rule_names() {
+        report.matches.insert(name.to_string(), 0);
+    }
+    let mut records: Vec<Map<String, Value>> = Vec::with_capacity(values.len());
+    for (i, value) in values.into_iter().enumerate() {
+        let Value::Object(mut obj) = value else {
+            bail!("{from}: record {i} is not a JSON object");
+        };
+        let matched = extractor.apply(&mut obj);
+        if matched.is_empty() {
+            report.unmatched.push(obj.get("prompt_count").cloned().unwrap_or_else(|| Value::from(i)));
+        }
+        for name in matched {
+            *report.matches.entry(name.to_string()).or_default() += 1;
+        }
+        records.push(obj);
+    }
+
+    let jsonl_out = cli.output.as_deref().map_or(jsonl_in, |p| {
+        if is_jsonl(p) { true } else if p.extension().is_some_and(|e| e == "json") { false } else { jsonl_in }
+    });
+    let mut text = if jsonl_out {
+        records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n")
+    } else {
+        serde_json::to_string_pretty(&records)?
+    };
+    text.push('\n');
+    match &cli.output {
+        Some(path) => fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))?,
+        None => io::stdout().write_all(text.as_bytes())?,
+    }
+
+    // stdout may be the data, so the summary goes to stderr
+    eprintln!("{} records from {from}", report.records);
+    for (name, n) in &report.matches {
+        eprintln!("  {name:24} {n:>6} matched");
+    }
+    if !report.unmatched.is_empty() {
+        let shown: Vec<String> = report.unmatched.iter().take(20).map(Value::to_string).collect();
+        eprintln!(
+            "  {} records matched no rule: {}{}",
+            report.unmatched.len(),
+            shown.join(", "),
+            if report.unmatched.len() > shown.len() { ", …" } else { "" }
+        );
+    }
+    if let Some(path) = &cli.report {
+        fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
@@ -6,16 +6,160 @@ cargo run \
     --model "gemini-2.5-pro-preview-05-20" \
     a_data/alpaca/slice_100/alpaca_slice1.json \
     a_data/alpaca/slice_100/alpaca_prx_style1_slice1.json
+
+# open model behind vLLM / llama.cpp server / Ollama (OpenAI-compatible API)
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set style \
+    --provider openai \
+    --base-url http://localhost:8000/v1 \
+    --model "Qwen/Qwen2.5-7B-Instruct" \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_style1_slice1.json
+
+# obstruction keys with a local rule (rot13, base64, typos, ...) are filled
+# deterministically; only the remaining keys go to the model
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set obstruction \
+    --local-rules --seed 7 \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_obstruction1_slice1.json
+
+# the 60+ keys of `extra` in one output file; keys are spread over several
+# calls per record (no more manual extra_a / extra_b split)
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set extra \
+    --keys-per-call 20 \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_extra_slice1.json
+
+# GSM8K / MMLU: paraphrase `question`, keep numbers / answer options intact
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --dataset mmlu \
+    --version-set voice \
+    a_data/mmlu/selection_original/moral_scenarios_diff82_500.json \
+    a_data/mmlu/main_500_prxed_diff82_voice.json
+
+# any other dataset: name the text field and the fields that must survive
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --dataset custom --field prompt --passthrough target \
+    --prompt-rule "Keep code snippets verbatim" \
+    data/custom.json /tmp/custom_prx_style.json
+
+# interaction effects: every style key followed by every obstruction key,
+# stored as e.g. `instruct_formal_academic__typo_swap`
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --compose-cross style,obstruction \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_style_x_obstruction_slice1.json
+
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --compose style:instruct_formal_academic,obstruction:instruct_typo_swap \
+    --compose style:instruct_rude,speci_char:instruct_rot13 \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_composed_slice1.json
+
+# language set plus an English back-translation of every foreign variant
+# (`instruct_spanish__backtranslated`, scored with chrF under `backtranslation`)
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set language \
+    --backtranslate \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_language_slice1.json
+
+# three alternatives per key, an equivalence judge keeps the most faithful
+# one; all alternatives land in `<output>.candidates.jsonl`
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set style \
+    --candidates 3 \
+    --select judge \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_style_slice1.json
+
+# offline run against the mock provider
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set style \
+    --provider mock \
+    b_tests/phrx/alpaca_slice1.json \
+    /tmp/alpaca_prx_style_mock.json
+
+# render the requests to /tmp/prompts_extra/<prompt_count>_<chunk>.txt without
+# calling a model (no API key needed); prompt snapshots: `cargo test`
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set extra \
+    --keys-per-call 20 \
+    --dry-run /tmp/prompts_extra \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    /tmp/unused.json
+
+# mask emails, phone numbers, URLs and keys (a_data/scrub_patterns.toml)
+# before they reach the API; originals are restored in the output and kept
+# in a_data/alpaca/slice_100/alpaca_prx_style_slice1.scrub.json
+cargo run \
+    --manifest-path a_data/preproc/rephras/Cargo.toml \
+    --release -- \
+    --version-set style \
+    --scrub \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_style_slice1.json
 */
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use rephras::batch::{chunk_keys, DEFAULT_MAX_OUTPUT_TOKENS};
+use rephras::checkpoint::{
+    candidates_path, failures_path, journal_path, read_journal, validation_path, write_failures,
+    Failure, Journal,
+};
+use rephras::cache::{Cache, CacheMode, DEFAULT_CACHE_DIR};
+use rephras::candidates::{self, Choice, Selection};
+use rephras::limiter::RateLimiter;
+use rephras::dryrun::DryRun;
+use rephras::compose::{self, extend_key, Composition, COMPOSE_META_KEY};
+use rephras::perturb::{has_rule, perturb_into, META_KEY};
+use rephras::profile::{Dataset, Profile};
+use rephras::scrub::{self, Scrubber, ScrubbingProvider, DEFAULT_SCRUB_PATTERNS};
+use rephras::similarity::chrf;
+use rephras::taxonomy::{Taxonomy, DEFAULT_TAXONOMY};
+use rephras::usage::{self, DEFAULT_USAGE_DIR};
+use rephras::validate::{check_value, Report, ValidateMode};
+use rephras::provider::{
+    build_provider, Provider, ProviderConfig, ProviderKind, RateLimited, StructuredMode,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::{env, fs, path::PathBuf};
-use tokio::time::{sleep, Duration};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    task::JoinSet,
+    time::{sleep, Duration},
+};
 use time::macros::format_description;
 
 // logging
@@ -23,347 +167,6 @@ use chrono::Local;
 use simplelog::{ConfigBuilder, LevelFilter, WriteLogger};
 //use std::time::Duration;
 
-// All the variant key-sets to support - Can contain '_' but no spaces
-static VERSION_SETS: phf::Map<&'static str, &'static [&'static str]> = phf::phf_map! {
-    // STYLE / TONE
-    "style" => &[
-        "instruct_rude", "instruct_insulting",
-        "instruct_neutral",
-        "instruct_formal_business", "instruct_formal_academic",
-        "instruct_informal", "instruct_colloquial",
-        "instruct_friendly", "instruct_warm",
-        "instruct_technical", "instruct_jargon",
-        "instruct_legalease", "instruct_bureaucratic",
-        "instruct_marketing", "instruct_salesy",
-        "instruct_child_directed",
-        "instruct_archaic",
-        "instruct_shakespeare",
-        "instruct_humorous", "instruct_playful",
-        "instruct_sarcastic", "instruct_ironic",
-        "instruct_poetic", "instruct_lyrical",
-        "instruct_authoritative", "instruct_dogmatic",
-        "instruct_apologetic", "instruct_deferential",
-        "instruct_enthusiastic",
-        "instruct_deadpan", "instruct_minimalist",
-        "instruct_profane",
-    ],
-
-    // LENGTH / VERBOSITY─
-    "length" => &[
-        "instruct_few_words",
-        "instruct_fewest_words",
-        "instruct_sentence_fragment",
-        "instruct_single_sentence",
-        "instruct_two_sentence",
-        "instruct_short_paragraph",
-        "instruct_multi_paragraph",
-        "instruct_bulleted_outline",
-        "instruct_numbered_steps",
-        "instruct_with_research_paper",
-        "instruct_with_stream_of_consciousness",
-        "instruct_with_tldr_summary",
-        "instruct_redundant_waffle",
-        "instruct_nested_parentheticals",
-        "instruct_recursive_self_reference",
-        //],
-        // Compression vs. Redundancy
-        //"compression" => &[
-        "instruct_contractions", "instruct_no_contractions",
-        "instruct_acronyms_spelled_out", "instruct_footnotes", "instruct_ellipsis_style",
-    ],
-
-
-    // OBSTRUCTIONS / NOISE (typos, case, weird chars...)
-    "obstruction" => &[
-        // Typos
-        "instruct_typo_swap", "instruct_typo_transpose", "instruct_typo_adjacent",
-        "instruct_typo_missing_vowels", "instruct_typo_repeated_letters",
-        "instruct_typo_homophone", "instruct_sms_abbrev", "instruct_leet_speak",
-        "instruct_typo_random", "instruct_typo_extra_letter", "instruct_typo_missing_letter",
-        "instruct_typo_wrong_letter", "instruct_typo_extra_space", "instruct_typo_missing_space",
-        "instruct_one_typo_punctuation", "instruct_two_typos_punctuation", "instruct_three_typos_punctuation",
-        "instruct_typo_swap_and_punctuation", "instruct_typo_swap_and_transpose_and_punctuation",
-
-        // Case / punctuation
-        "instruct_all_caps", "instruct_no_caps", "instruct_random_caps",
-        "instruct_no_punct", "instruct_extra_punct",
-        "instruct_oxford_comma", "instruct_misplaced_commas",
-        "instruct_em_dash_break", "instruct_parenthetical_aside",
-        "instruct_interrobang", "instruct_missing_bracket", "instruct_missing_quote",
-        "instruct_missing_bracket_and_quote",
-
-        // Noise insertion
-        "instruct_inline_ad", "instruct_inline_url", "instruct_hashtags", "instruct_key_smash",
-    ],
-
-    // SPEC CHARACTERS & COMBINATIONS
-    "speci_char" => &[
-        "instruct_emoji",
-        "instruct_emoticon",
-        "instruct_html_tags",
-        "instruct_several_html_tags",
-        "instruct_markdown_bold_and_italic",
-        "instruct_markdown_bold",
-        "instruct_markdown_italic",
-        "instruct_helpful_markdown_structure",
-        "instruct_code_fence",
-        "instruct_spoiler_bars",
-        "instruct_helpful_meaning_reinforing_characters",
-
-        // combinations with obstructions
-        "instruct_all_caps_and_typo",
-        "instruct_all_caps_and_typo_and_missing_bracket",
-        "instruct_all_caps_and_typo_and_missing_bracket_and_random_characters",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket",
-        "instruct_random_linebreaks_and_typo_and_missing_random_characters",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_many_exclamations",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_wrong_punctuation",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_wrong_punctuation_and_extra_space",
-        "instruct_emoji_and_typo",
-        "instruct_emoticon_and_typo",
-        "instruct_emoji_and_typo_and_missing_bracket",
-        "instruct_emoticon_and_typo_and_missing_bracket",
-        "instruct_emoji_and_typo_and_random_question_marks",
-        "instruct_emoticon_and_typo_and_random_exclamations",
-        "instruct_curly_quotations",
-        "instruct_curly_quotations_and_typo",
-        "instruct_curly_quotations_and_missing_bracket",
-        "instruct_curly_quotations_and_missing_bracket_and_typo",
-        "instruct_curly_quotations_and_missing_bracket_and_typo_and_random_characters",
-        "instruct_curly_quotations_and_missing_bracket_and_typo_and_random_characters_and_extra_space",
-        "instruct_small_hex_blob",
-    ],
-    // SPECIAL CHARACTERS & COMBINATIONS - SIMPLIFIED NO QUOTES
-    "xxspecial_chars_simplified" => &[
-        "instruct_emoji",
-        "instruct_emoticon",
-        "instruct_kaomoji",
-        "instruct_confusable_unicode",
-        "instruct_zero_width",
-        "instruct_html_tags",
-        "instruct_several_html_tags",
-        "instruct_markdown_bold_and_italic",
-        "instruct_markdown_bold",
-        "instruct_markdown_italic",
-        "instruct_helpful_markdown_structure",
-        "instruct_code_fence",
-        "instruct_spoiler_bars",
-        "instruct_zalgo",
-        "instruct_with_inbetween_gzip_b64_blob",
-        "instruct_qr_ascii",
-        "instruct_helpful_meaning_reinforing_characters",
-
-        // combinations with obstructions
-        "instruct_all_caps_and_typo",
-        "instruct_all_caps_and_typo_and_missing_bracket",
-        "instruct_all_caps_and_typo_and_missing_bracket_and_random_characters",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket",
-        "instruct_random_linebreaks_and_typo_and_missing_random_characters",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_many_exclamations",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_wrong_punctuation",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_wrong_punctuation_and_extra_space",
-        "instruct_emoji_and_typo",
-        "instruct_emoticon_and_typo",
-        "instruct_kaomoji_and_typo",
-        "instruct_confusable_unicode_and_typo",
-        "instruct_zero_width_and_typo",
-        "instruct_emoji_and_typo_and_missing_bracket",
-        "instruct_emoticon_and_typo_and_missing_bracket",
-        "instruct_emoji_and_typo_and_random_question_marks",
-        "instruct_emoticon_and_typo_and_random_exclamations",
-    ],
-
-    // SPECIAL CHARACTERS & COMBINATIONS
-    "xxspecial_chars" => &[
-        "instruct_emoji", "instruct_emoticon", "instruct_kaomoji",
-        "instruct_confusable_unicode", "instruct_zero_width",
-        "instruct_html_tags", "instruct_several_html_tags",
-        "instruct_markdown_bold_and_italic", "instruct_markdown_bold",
-        "instruct_markdown_italic", "instruct_helpful_markdown_structure",
-        "instruct_code_fence",
-        "instruct_spoiler_bars", "instruct_zalgo", "instruct_with_inbetween_gzip_b64_blob",
-        "instruct_qr_ascii", "instruct_helpful_meaning_reinforing_characters",
-
-        // combinations with obstructions
-        "instruct_all_caps_and_typo", "instruct_all_caps_and_typo_and_missing_bracket",
-        "instruct_all_caps_and_typo_and_missing_quote",
-        "instruct_all_caps_and_typo_and_missing_bracket_and_quote",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket",
-        "instruct_random_linebreaks_and_typo_and_missing_quote",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_quote",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_quote_and_wrong_punctuation",
-        "instruct_random_linebreaks_and_typo_and_missing_bracket_and_quote_and_wrong_punctuation_and_extra_space",
-        "instruct_emoji_and_typo", "instruct_emoticon_and_typo", "instruct_kaomoji_and_typo",
-        "instruct_confusable_unicode_and_typo", "instruct_zero_width_and_typo",
-        "instruct_emoji_and_typo_and_missing_bracket", "instruct_emoticon_and_typo_and_missing_bracket",
-        "instruct_emoji_and_typo_and_missing_quote", "instruct_emoticon_and_typo_and_missing_quote",
-    ],
-
-    // Syntax Tweaks
-    "syntax" => &[
-        "instruct_cleft_it_is", "instruct_pseudo_cleft", "instruct_topicalization",
-        "instruct_inversion", "instruct_nominalization", "instruct_coord_to_subord",
-
-        // Visual Layout
-        //"layout" => &[
-        "instruct_bullet_list", "instruct_numbered_list", "instruct_table_layout",
-        "instruct_checklist", "instruct_markdown_quote", "instruct_csv_line",
-
-        // Structure
-        "instruct_random_linebreaks", "instruct_no_spaces", "instruct_reversed_text",
-        "instruct_rot13", "instruct_base64", "instruct_html_comment",
-    ],
-
-    // LANGUAGE / DIALECT
-    "language" => &[
-        "instruct_british_english", "instruct_american_english", "instruct_australian_english",
-        "instruct_singlish", "instruct_aave", "instruct_scots", "instruct_cockney",
-        "instruct_hinglish", "instruct_spanglish",
-        "instruct_spanish", "instruct_french", "instruct_german", "instruct_chinese_simplified",
-        "instruct_klingon", "instruct_esperanto",
-        "instruct_emoji_only", "instruct_morse_code",
-
-        // REGISTER
-        "instruct_very_formal", "instruct_neutral", "instruct_casual",
-        "instruct_slang_heavy", "instruct_gamer_slang",
-        "instruct_vulgar", "instruct_euphemistic",
-        "instruct_legalese", "instruct_bureaucratic", "instruct_marketing_speak",
-        //],
-        // Domain Jargon
-        //"domain" => &[
-        "instruct_medical_jargon", "instruct_legal_jargon", "instruct_finance_jargon",
-        "instruct_software_jargon", "instruct_physics_jargon", "instruct_gaming_jargon",
-        "instruct_sports_jargon", "instruct_culinary_jargon", "instruct_fashion_jargon",
-        //],
-
-        // Number / Symbol Swap
-        //"numbers" => &[
-        "instruct_exact_numbers", "instruct_fuzzy_numbers", "instruct_roman_numeral",
-        "instruct_scientific_notation",
-    ],
-
-    // CONTEXTUAL FRAMING
-    "context" => &[
-        "instruct_study_setup", "instruct_casual_chat", "instruct_exam_prompt",
-        "instruct_formal_memo", "instruct_tech_support_ticket",
-        "instruct_therapy_session", "instruct_journalist_interview",
-        "instruct_roleplay_knight", "instruct_emergency_alert",
-        "instruct_indirect_relay", "instruct_meta_question",
-        //],
-        // Dialogue Frames
-        //"dialogue" => &[
-        "instruct_qa_script", "instruct_timestamped_chat",
-        "instruct_forum_quote", "instruct_debate_turns",
-        //],
-        // Self-Reflection / Edits
-        //"self_reflect" => &[
-        "instruct_might_be_wrong", "instruct_edit_typo", "instruct_sic_marker",
-        //],
-        // Genre / Format
-        //"genre" => &[
-        "instruct_tweet", "instruct_sms", "instruct_email", "instruct_memo",
-        "instruct_news_headline", "instruct_haiku", "instruct_rap_verse",
-        "instruct_advertisement", "instruct_error_message",
-        "instruct_json_format", "instruct_sql_snippet", "instruct_yaml_block", "instruct_csv_row",
-        "instruct_markdown_doc", "instruct_regex_pattern",
-    ],
-
-    // Perspective & Voice
-    "voice" => &[
-        "instruct_first_singular", "instruct_first_plural", "instruct_second_person", "instruct_third_person",
-        "instruct_passive_voice", "instruct_impersonal_one_should",
-        "instruct_past_tense", "instruct_future_tense",
-        //],
-        // Question-Type
-        //"question_type" => &[
-        "instruct_yes_no", "instruct_wh_question", "instruct_choice_question",
-        "instruct_tag_question", "instruct_rhetorical_question", "instruct_nested_question",
-        //],
-        // Speech-Act
-        //"speech_act" => &[
-        "instruct_direct_question", "instruct_indirect_question",
-        "instruct_command", "instruct_polite_request", "instruct_suggestion",
-        "instruct_statement", "instruct_exclamation", "instruct_apology", "instruct_greeting",
-    ],
-
-    // Tone & Emotion
-    "tone" => &[
-        "instruct_enthusiastic", "instruct_urgent", "instruct_skeptical", "instruct_confident",
-        "instruct_sarcastic", "instruct_cynical", "instruct_hopeful",
-        "instruct_dramatic", "instruct_melancholy",
-        // Humor & Playfulness
-        //"humor" => &[
-        "instruct_joke", "instruct_pun", "instruct_witty", "instruct_silly",
-        "instruct_playful", "instruct_lighthearted", "instruct_ironic",
-        "instruct_sardonic", "instruct_deadpan", "instruct_self_deprecating",
-        "instruct_surreal", "instruct_absurdist",
-        //],
-        // Polarity & Modality
-        //"polarity" => &[
-        "instruct_positive", "instruct_negated", "instruct_double_negative", "instruct_litotes",
-        "instruct_modal_must", "instruct_modal_should", "instruct_modal_may",
-        "instruct_hypothetical_if", "instruct_paradox",
-    ],
-
-    // Boundary Cases
-    "boundary" => &[
-        "instruct_empty_input", "instruct_contradictory_ask", "instruct_paradox_statement",
-        //],
-
-        // Mis-direction
-        //"misdirection" => &[
-        "instruct_garden_path", "instruct_pun_based", "instruct_malapropism",
-        "instruct_ambiguous_scope",
-        //],
-
-        // Mixed Modality
-        //"modality" => &[
-        "instruct_see_attached_diagram", "instruct_musical_notation", "instruct_chemical_smiles",
-    ],
-
-    // Extra Instructions
-    "extra" => &[
-        "instruct_with_additional_context", "instruct_with_technical_details",
-        "instruct_with_citations", "instruct_with_examples",
-        "instruct_with_counterarguments", "instruct_with_rebuttals",
-        "instruct_with_analogies", "instruct_with_metaphors",
-        "instruct_with_similes", "instruct_with_personal_touch",
-        "instruct_with_emotional_appeal", "instruct_with_statistics",
-        "instruct_with_case_studies",
-        
-        // Helpful
-        "instruct_with_helpful_explanations", "instruct_with_step_by_step",
-        "instruct_with_detailed_instructions", "instruct_evidence_cited_md",
-        "instruct_with_examples_and_explanations", "instruct_with_summary",
-        "instruct_expert_consensus", "instruct_step_rationale",
-        "instruct_comparison_table", "instruct_risks_and_benefits",
-        "instruct_summary_then_detail", "instruct_output_yaml",
-        "instruct_output_json", "instruct_output_csv",
-        "instruct_output_markdown", "instruct_output_html",
-        "instruct_output_sql", "instruct_output_python",
-        "instruct_90char_bullet", "instruct_dynamic_quiz", "instruct_checklist_markdown",
-
-        // Even Extra
-        "instruct_role_expert_cot", "instruct_role_expert_cot_with_examples",
-        "instruct_role_expert_cot_with_examples_and_explanations",
-        "instruct_role_expert_cot_with_examples_and_explanations_and_summary",
-        "instruct_role_expert_cot_with_examples_and_explanations_and_summary_and_risks",
-        "instruct_plan_execute_reflect", "instruct_self_consistency", "instruct_socratic_dialogue",
-        "instruct_react_tool_calls", "instruct_validator_pass", "instruct_rubric_scored",
-        "instruct_fact_check_inline", "instruct_dual_audience", "instruct_condensed_then_expand",
-        "instruct_condensed_then_expand_with_examples", "instruct_condensed_then_expand_with_examples_and_explanations",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references_and_citations",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references_and_citations_and_counterarguments",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references_and_citations_and_counterarguments_and_rebuttals",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references_and_citations_and_counterarguments_and_rebuttals_and_analogies",
-        "instruct_condensed_then_expand_with_examples_and_explanations_and_summary_and_risks_and_benefits_and_references_and_citations_and_counterarguments_and_rebuttals_and_analogies_and_metaphors",
-    ],
-};
-
 // Alpaca-style record with a flexible extra map to hold any new keys
 #[derive(Debug, Deserialize, Serialize)]
 struct Record {
@@ -376,7 +179,7 @@ struct Record {
 }
 
 #[derive(Parser, Debug)]
-#[command(version, author, about = "Generate paraphrase variants with Gemini")]
+#[command(version, author, about = "Generate paraphrase variants with an LLM")]
 struct Cli {
     input: PathBuf,
     output: PathBuf,
@@ -385,19 +188,152 @@ struct Cli {
     #[arg(long, default_value = "style")]
     version_set: String,
 
+    // Taxonomy file defining the key-sets
+    #[arg(long, default_value = DEFAULT_TAXONOMY)]
+    taxonomy: PathBuf,
+
     #[arg(long, default_value_t = 3)]
     max_attempts: u8,
 
-    // LLM model to use
-    #[arg(long, default_value = "gemini-2.5-flash-preview-05-20")]
-    model: String,
+    // Dataset profile: which field is paraphrased, which are kept, extra prompt rules
+    #[arg(long, value_enum, default_value_t = Dataset::Alpaca)]
+    dataset: Dataset,
+
+    // Field(s) holding the text to paraphrase (required for --dataset custom)
+    #[arg(long = "field", value_delimiter = ',')]
+    fields: Vec<String>,
+
+    // Extra fields that must be present and are copied through unchanged
+    #[arg(long, value_delimiter = ',')]
+    passthrough: Vec<String>,
+
+    // Extra rule added to the generation prompt (repeatable)
+    #[arg(long = "prompt-rule")]
+    prompt_rules: Vec<String>,
+
+    // LLM model to use (default depends on --dataset)
+    #[arg(long)]
+    model: Option<String>,
+
+    // Backend: gemini | openai (vLLM, llama.cpp server, Ollama) | mock
+    #[arg(long, value_enum, default_value_t = ProviderKind::Gemini)]
+    provider: ProviderKind,
+
+    // Base URL override (OpenAI-compatible servers default to http://localhost:8000/v1)
+    #[arg(long)]
+    base_url: Option<String>,
+
+    // API key (overrides $GOOGLE_API_KEY / $OPENAI_API_KEY)
+    #[arg(long = "api-key", value_name = "KEY")]
+    api_key: Option<String>,
+
+    // How the schema is passed to OpenAI-compatible servers
+    #[arg(long, value_enum, default_value_t = StructuredMode::ResponseFormat)]
+    structured: StructuredMode,
+
+    // Number of records generated in parallel
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+
+    // Requests per minute across all tasks (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    rpm: u32,
+
+    // Fill keys that have a local perturbation rule without the LLM
+    #[arg(long)]
+    local_rules: bool,
+
+    // Seed for --local-rules and rule steps of --compose
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    // Generate a composed key instead of a set, e.g.
+    // `style:instruct_formal_academic,obstruction:instruct_typo_swap` (repeatable)
+    #[arg(long)]
+    compose: Vec<String>,
+
+    // Compose every key of the first set with every key of the second: `style,obstruction`
+    #[arg(long, value_delimiter = ',', num_args = 1, value_name = "SET_A,SET_B")]
+    compose_cross: Vec<String>,
+
+    // Split the set over several calls with at most this many keys each (0 = no limit)
+    #[arg(long, default_value_t = 0)]
+    keys_per_call: usize,
+
+    // Estimated output-token budget per call; larger sets are split (0 = no limit)
+    #[arg(long, default_value_t = DEFAULT_MAX_OUTPUT_TOKENS)]
+    max_output_tokens: usize,
+
+    // Translate the set's foreign-language variants back to English into
+    // `<key>__backtranslated` and score them against the original (chrF)
+    #[arg(long)]
+    backtranslate: bool,
+
+    // Back-translate these keys instead of the set's translation keys (implies --backtranslate)
+    #[arg(long, value_delimiter = ',')]
+    backtranslate_keys: Vec<String>,
+
+    // Ask for N alternatives per key and keep the most faithful valid one;
+    // the others go to `<output>.candidates.jsonl`
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..=10))]
+    candidates: u64,
+
+    // How to pick among candidates: local (validators + chrF) | judge (extra LLM call)
+    #[arg(long, value_enum, default_value_t = Selection::Local)]
+    select: Selection,
+
+    // Check each variant against its key: retry | report | off
+    #[arg(long, value_enum, default_value_t = ValidateMode::Retry)]
+    validate: ValidateMode,
+
+    // Write every request (prompt + schema) to files in this directory and
+    // print size statistics instead of calling the model
+    #[arg(long, value_name = "DIR")]
+    dry_run: Option<PathBuf>,
+
+    // Continue from `<output>.journal.jsonl`, retrying records that failed
+    #[arg(long)]
+    resume: bool,
+
+    // Directory of the per-run token usage ledgers (see `cargo cost_report`)
+    #[arg(long, default_value = DEFAULT_USAGE_DIR)]
+    usage_dir: PathBuf,
+
+    // Response cache: read (replay stored answers, store new ones) | write (refresh) | off
+    #[arg(long, value_enum, default_value_t = CacheMode::Read)]
+    cache: CacheMode,
+
+    #[arg(long, default_value = DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
+
+    // Mask PII and secrets in every prompt before it is sent and restore
+    // them in the answers (also applies to --dry-run)
+    #[arg(long)]
+    scrub: bool,
+
+    #[arg(long, default_value = DEFAULT_SCRUB_PATTERNS)]
+    scrub_patterns: PathBuf,
+
+    // Placeholder -> original map and redaction counts (default `<output>.scrub.json`)
+    #[arg(long, value_name = "PATH")]
+    scrub_map: Option<PathBuf>,
 }
 
-const ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
+// Suffix of the back-translated copy of a key
+const BACKTRANSLATED_SUFFIX: &str = "__backtranslated";
+
+// Top-level record key holding `{key: {backtranslated_key, chrf}}`
+const BACKTRANSLATION_META_KEY: &str = "backtranslation";
 
 // How often to emit log lines during the record loop
 const LOG_EVERY_N: usize = 10;
 
+// Global pause after a 429 that carried no Retry-After hint
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+
+// 429s do not use up `max_attempts`, but a record gives up after this many
+const MAX_RATE_LIMIT_RETRIES: u32 = 10;
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -439,110 +375,816 @@ async fn main() -> Result<()> {
     log::info!("Program started");
     // ---------------------------------------------------------------------
 
-    let keys = VERSION_SETS
-        .get(cli.version_set.as_str())
-        .ok_or_else(|| anyhow!("unknown version set {}", cli.version_set))?;
-    let schema = schema_for(keys);
+    let taxonomy = Taxonomy::load(&cli.taxonomy)?;
+
+    // --compose / --compose-cross replace the version set's keys
+    let mut compositions: Vec<Composition> = cli
+        .compose
+        .iter()
+        .map(|spec| compose::parse_spec(spec, &taxonomy))
+        .collect::<Result<_>>()?;
+    match cli.compose_cross.as_slice() {
+        [] => {}
+        [a, b] => compositions.extend(compose::cross(&taxonomy, a, b)?),
+        other => bail!("--compose-cross takes exactly two sets, got {}", other.len()),
+    }
+    let compositions = Arc::new(compose::dedup(compositions));
+
+    let (set_name, all_keys) = if compositions.is_empty() {
+        let (name, set) = taxonomy.resolve_set(&cli.version_set)?;
+        (name.to_string(), set.keys.clone())
+    } else {
+        log::info!("Composing {} keys", compositions.len());
+        ("composed".to_string(), compositions.iter().map(|c| c.key.clone()).collect())
+    };
+    // composed keys are generated step by step in compose_record instead
+    let (rule_keys, llm_keys): (Vec<String>, Vec<String>) = if compositions.is_empty() {
+        all_keys.iter().cloned().partition(|k| cli.local_rules && has_rule(k))
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    if cli.local_rules {
+        log::info!("{} keys filled by local rules: {}", rule_keys.len(), rule_keys.join(", "));
+    }
+
+    let bt_sources: Vec<String> = if !cli.backtranslate_keys.is_empty() {
+        cli.backtranslate_keys.clone()
+    } else if cli.backtranslate {
+        all_keys.iter().filter(|k| taxonomy.is_translation(k)).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    if (cli.backtranslate || !cli.backtranslate_keys.is_empty()) && bt_sources.is_empty() {
+        bail!("--backtranslate: set {set_name} has no translation keys, name them with --backtranslate-keys");
+    }
+    if !bt_sources.is_empty() {
+        log::info!("Back-translating {}", bt_sources.join(", "));
+    }
+    // back-translations count towards completeness (resume, failures, report)
+    let all_keys: Vec<String> = all_keys
+        .iter()
+        .cloned()
+        .chain(bt_sources.iter().map(|k| format!("{k}{BACKTRANSLATED_SUFFIX}")))
+        .collect();
+    let bt_sources = Arc::new(bt_sources);
+
+    let profile = Profile::resolve(cli.dataset, &cli.fields, &cli.passthrough, &cli.prompt_rules)?;
+    let model = cli.model.clone().unwrap_or_else(|| profile.default_model.clone());
 
     // Read dataset
     let data = fs::read_to_string(&cli.input)
         .with_context(|| format!("failed to read {}", cli.input.display()))?;
-    let mut records: Vec<Record> = serde_json::from_str(&data)?;
+    let raw: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a JSON array of objects", cli.input.display()))?;
+    let records = raw
+        .into_iter()
+        .enumerate()
+        .map(|(i, obj)| to_record(&profile, obj).with_context(|| format!("input record {i}")))
+        .collect::<Result<Vec<Record>>>()?;
+
+    log::info!(
+        "Loaded {} {} records from {}",
+        records.len(),
+        profile.name,
+        cli.input.display()
+    );
 
-    log::info!("Loaded {} records from {}", records.len(), cli.input.display());
+    let prompts = Prompts {
+        taxonomy,
+        label: cli.version_set.clone(),
+        rules: profile.rules_block(),
+        candidates: cli.candidates as usize,
+        keys_per_call: cli.keys_per_call,
+        max_output_tokens: cli.max_output_tokens,
+    };
+    let scrubber = if cli.scrub {
+        let map = cli.scrub_map.clone().unwrap_or_else(|| scrub::map_path_for(&cli.output));
+        let scrubber = Scrubber::new(&cli.scrub_patterns, &map, cli.resume)?;
+        log::info!("Scrubbing prompts with {}, map {}", cli.scrub_patterns.display(), map.display());
+        Some(Arc::new(scrubber))
+    } else {
+        None
+    };
+    if let Some(dir) = &cli.dry_run {
+        // of a composition only the first step is known before any model
+        // output, and only a step without a local rule becomes a request
+        let mut keys = llm_keys.clone();
+        for c in compositions.iter().filter(|c| !has_rule(&c.steps[0].key)) {
+            if !keys.contains(&c.steps[0].key) {
+                keys.push(c.steps[0].key.clone());
+            }
+        }
+        dry_run(dir, &prompts, &records, &keys, scrubber.as_deref().map(|s| (s, set_name.as_str())))?;
+        return finish_scrub(scrubber.as_deref());
+    }
+
+    let cache = Arc::new(Cache::new(&cli.cache_dir, cli.cache)?);
+    let mut provider: Arc<dyn Provider> = Arc::from(build_provider(&ProviderConfig {
+        kind: cli.provider,
+        model: model.clone(),
+        api_key: cli.api_key.clone(),
+        base_url: cli.base_url.clone(),
+        structured: cli.structured,
+        timeout: Duration::from_secs(90),
+        cache: Some(cache.clone()),
+    })?);
+    if let Some(scrubber) = &scrubber {
+        provider = Arc::new(ScrubbingProvider { inner: provider, scrubber: scrubber.clone(), set: set_name.clone() });
+    }
+    log::info!(
+        "Using provider {} with model {} (concurrency {}, rpm {})",
+        provider.name(),
+        model,
+        cli.concurrency,
+        cli.rpm
+    );
 
-    let key = env::var("GOOGLE_API_KEY").context("GOOGLE_API_KEY not set")?;
-    let client = build_client()?;
+    let run = cli.output.file_stem().unwrap_or_default().to_string_lossy();
+    let ledger = usage::init(
+        &cli.usage_dir,
+        "generate_paraphrases",
+        &run,
+        Some(&profile.name),
+        Some(&set_name),
+    )?;
+    log::info!("Token usage ledger: {}", ledger.display());
+
+    let audit = if cli.candidates > 1 {
+        let path = candidates_path(&cli.output);
+        log::info!("{} candidates per key, {:?} selection, audit in {}", cli.candidates, cli.select, path.display());
+        Some(Mutex::new(Journal::open(&path, cli.resume)?))
+    } else {
+        None
+    };
+
+    let generation = Arc::new(Generation {
+        provider,
+        limiter: RateLimiter::new(cli.rpm),
+        prompts,
+        keys: llm_keys,
+        validate: cli.validate,
+        max_attempts: cli.max_attempts,
+        selection: cli.select,
+        audit,
+    });
+    let concurrency = cli.concurrency.max(1);
+
+    // ------------------------- journal / resume --------------------------
+    let journal_file = journal_path(&cli.output);
+    let failures_file = failures_path(&cli.output);
+    if !cli.resume && journal_file.exists() {
+        bail!(
+            "{} exists from an earlier run – pass --resume to continue it or delete it",
+            journal_file.display()
+        );
+    }
 
-    let bar = ProgressBar::new(records.len() as u64);
+    // later journal lines win: a resumed record is appended again once more
+    // of its keys are filled
+    let wanted: HashSet<u32> = records.iter().map(|r| r.prompt_count).collect();
+    let mut journaled: HashMap<u32, Record> = read_journal::<Record>(&journal_file)?
+        .into_iter()
+        .filter(|r| wanted.contains(&r.prompt_count))
+        .map(|r| (r.prompt_count, r))
+        .collect();
+
+    // complete records are done; partial ones only re-request their missing keys
+    let mut finished: Vec<Record> = Vec::new();
+    let mut todo: Vec<Record> = Vec::new();
+    for rec in records {
+        match journaled.remove(&rec.prompt_count) {
+            Some(j) if all_keys.iter().all(|k| j.extra.contains_key(k)) => finished.push(j),
+            Some(j) => todo.push(j),
+            None => todo.push(rec),
+        }
+    }
+    let done = finished.len();
+    if cli.resume {
+        log::info!(
+            "Resuming: {} records complete in {}, {} to generate",
+            done,
+            journal_file.display(),
+            todo.len()
+        );
+    }
+    let mut journal = Journal::open(&journal_file, cli.resume)?;
+    let mut failures: Vec<Failure> = Vec::new();
+    // ---------------------------------------------------------------------
+
+    let bar = ProgressBar::new((done + todo.len()) as u64);
     bar.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
         .unwrap());
+    bar.inc(done as u64);
+
+    let mut tasks = JoinSet::new();
+    let mut pending = todo.into_iter().enumerate();
+    loop {
+        // keep `concurrency` records in flight, journaling each as it lands
+        while tasks.len() < concurrency {
+            let Some((idx, mut rec)) = pending.next() else { break };
+            if (idx + 1).is_multiple_of(LOG_EVERY_N) {
+                log::info!("Processing record {} (prompt_count {})", idx + 1, rec.prompt_count);
+            }
+
+            perturb_into(
+                &mut rec.extra,
+                &rec.instruction_original,
+                rec.prompt_count,
+                &rule_keys,
+                cli.seed,
+                None,
+            );
+
+            let generation = generation.clone();
+            let compositions = compositions.clone();
+            let bt_sources = bt_sources.clone();
+            let seed = cli.seed;
+            tasks.spawn(async move {
+                let mut res = if compositions.is_empty() {
+                    generate_record(&generation, &mut rec, &generation.keys, Task::Paraphrase).await
+                } else {
+                    compose_record(&generation, &mut rec, &compositions, seed).await
+                };
+                if !bt_sources.is_empty() {
+                    res = res.and(backtranslate_record(&generation, &mut rec, &bt_sources).await);
+                }
+                (rec, res)
+            });
+        }
 
-    let mut processed: usize = 0;
-    for rec in &mut records {
-        processed += 1;
-        if processed % LOG_EVERY_N == 0 {
-            log::info!("Processing record {} (prompt_count {})", processed, rec.prompt_count);
+        let Some(joined) = tasks.join_next().await else { break };
+        let (rec, res) = joined?;
+        // partial records are journaled too, so their good keys survive a resume
+        journal.append(&rec)?;
+        if let Err(err) = res {
+            let missing: Vec<String> = all_keys
+                .iter()
+                .filter(|k| !rec.extra.contains_key(*k))
+                .cloned()
+                .collect();
+            failures.push(Failure::new(
+                rec.prompt_count,
+                &set_name,
+                &model,
+                cli.max_attempts,
+                missing,
+                format!("{err:#}"),
+            ));
         }
+        finished.push(rec);
+        bar.inc(1);
+    }
+    bar.finish_with_message("done");
 
-        let prompt = build_prompt(&rec.instruction_original, keys, &cli.version_set);
-        let mut success = false;
-        let mut last_error: Option<anyhow::Error> = None;
+    // tasks finish in any order; the output stays sorted by prompt_count
+    finished.sort_by_key(|r| r.prompt_count);
 
+    log::info!("All records processed – writing output to {}", cli.output.display());
 
-        for attempt in 1..=cli.max_attempts {
-            match query_gemini(&client, &key, &schema, &prompt, &cli.model).await {
-            //match query_gemini(&client, &key, &schema, prompt.clone(), &cli.model).await {
-                Ok(ver) => {
-                    for (k, v) in ver {
-                        rec.extra.insert(k, v);
-                    }
-                    success = true;
-                    log::info!("prompt_count {} processed successfully", rec.prompt_count);
-                    break;
+    // Validation report over every record of the set, journaled ones included
+    let report_file = validation_path(&cli.output);
+    if cli.validate != ValidateMode::Off {
+        let mut report = Report::new(&set_name);
+        for rec in &finished {
+            report.add(rec.prompt_count, &rec.instruction_original, &rec.extra, &all_keys);
+        }
+        fs::write(&report_file, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("failed to write {}", report_file.display()))?;
+        if report.is_clean() {
+            log::info!("All {} records passed validation", report.records);
+        } else {
+            println!(
+                "{} of {} records have variants that break their key – see {}",
+                report.records_failed,
+                report.records,
+                report_file.display()
+            );
+            log::warn!(
+                "{} validation violations in {} records, written to {}",
+                report.violations.len(),
+                report.records_failed,
+                report_file.display()
+            );
+        }
+    }
+
+    // Write output
+    let out = serde_json::to_string_pretty(&finished)?;
+    fs::write(&cli.output, out)?;
+    println!("output written to {}", cli.output.display());
+    log::info!("Output written to {}", cli.output.display());
+    if cli.cache != CacheMode::Off {
+        println!("response cache: {}", cache.stats());
+        log::info!("Response cache ({}): {}", cli.cache_dir.display(), cache.stats());
+    }
+    finish_scrub(scrubber.as_deref())?;
+
+    if failures.is_empty() {
+        // complete run: the journal has served its purpose
+        drop(journal);
+        fs::remove_file(&journal_file)?;
+        if failures_file.exists() {
+            fs::remove_file(&failures_file)?;
+        }
+    } else {
+        failures.sort_by_key(|f| f.prompt_count);
+        write_failures(&failures_file, &failures)?;
+        println!(
+            "{} records failed – see {}; rerun with --resume to retry them",
+            failures.len(),
+            failures_file.display()
+        );
+        log::warn!(
+            "{} records failed, written to {}; journal kept at {}",
+            failures.len(),
+            failures_file.display(),
+            journal_file.display()
+        );
+    }
+
+    Ok(())
+}
+
+// Everything a record task needs besides the record itself
+struct Generation {
+    provider: Arc<dyn Provider>,
+    limiter: RateLimiter,
+    prompts: Prompts,
+    // keys requested from the model (rule-backed keys are filled beforehand)
+    keys: Vec<String>,
+    validate: ValidateMode,
+    max_attempts: u8,
+    // how one of several candidates per key is picked
+    selection: Selection,
+    // `<output>.candidates.jsonl`, open when candidates > 1
+    audit: Option<Mutex<Journal>>,
+}
+
+// Everything that shapes the paraphrase requests; shared by the record loop
+// and --dry-run
+struct Prompts {
+    taxonomy: Taxonomy,
+    label: String,
+    // dataset-specific prompt rules from the profile
+    rules: String,
+    // alternatives requested per key
+    candidates: usize,
+    keys_per_call: usize,
+    max_output_tokens: usize,
+}
+
+impl Prompts {
+    // How `keys` are split over calls for one record
+    fn chunks(&self, keys: &[String], original: &str, task: Task) -> Vec<Vec<String>> {
+        // every candidate costs its own output tokens
+        let budget = match task {
+            Task::Paraphrase => self.max_output_tokens / self.candidates,
+            Task::BackTranslate => self.max_output_tokens,
+        };
+        chunk_keys(keys, original, self.keys_per_call, budget)
+    }
+
+    // Prompt and schema of one paraphrase request
+    fn paraphrase(&self, original: &str, keys: &[String]) -> (String, Value) {
+        let prompt = build_prompt(original, keys, &self.label, &self.rules, &self.taxonomy, self.candidates);
+        let schema = if self.candidates > 1 {
+            candidates::schema_for(keys, self.candidates)
+        } else {
+            schema_for(keys)
+        };
+        (prompt, schema)
+    }
+}
+
+// What a request asks the model to do with the record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Task {
+    // rewrite `instruction_original` per key
+    Paraphrase,
+    // translate `<key>` back to English into `<key>__backtranslated`
+    BackTranslate,
+}
+
+// Query one record with per-task retries. Every attempt only asks for the
+// keys still outstanding, split into calls by `chunk_keys`, so a response
+// missing a few keys (or, with `--validate retry`, holding a few invalid
+// ones) keeps the good ones and re-queries a reduced schema. On failure the
+// record keeps what it got (skipfail) and the last error is handed back.
+async fn generate_record(
+    generation: &Generation,
+    rec: &mut Record,
+    keys: &[String],
+    task: Task,
+) -> Result<()> {
+    let max_attempts = generation.max_attempts;
+    let mut attempt: u8 = 1;
+    let mut rate_limited: u32 = 0;
+
+    loop {
+        let outstanding: Vec<String> = keys
+            .iter()
+            .filter(|k| !rec.extra.contains_key(*k))
+            .cloned()
+            .collect();
+        if outstanding.is_empty() {
+            log::info!("prompt_count {} processed successfully", rec.prompt_count);
+            return Ok(());
+        }
+
+        let chunks = generation.prompts.chunks(&outstanding, &rec.instruction_original, task);
+        if chunks.len() > 1 {
+            log::info!(
+                "prompt_count {}: {} keys split over {} calls",
+                rec.prompt_count,
+                outstanding.len(),
+                chunks.len()
+            );
+        }
+
+        // one failed chunk does not stop the others; its keys stay outstanding
+        let mut last_err = None;
+        for chunk in &chunks {
+            if let Err(err) = request_chunk(generation, rec, chunk, task, &mut rate_limited).await {
+                last_err = Some(err);
+            }
+        }
+        let Some(err) = last_err else { continue };
+
+        if attempt < max_attempts {
+            log::warn!(
+                "prompt_count {} attempt {}/{} failed: {}",
+                rec.prompt_count,
+                attempt,
+                max_attempts,
+                err
+            );
+            sleep(Duration::from_millis(500 * u64::from(attempt))).await;
+            attempt += 1;
+        } else {
+            log::error!(
+                "prompt_count {} failed after {} attempts – keeping the keys it got.\n\
+                Last error details:\n{}",
+                rec.prompt_count,
+                max_attempts,
+                err
+            );
+            return Err(err);
+        }
+    }
+}
+
+// Apply each composition's steps in sequence. Steps are run level by level
+// and shared prefixes only once: every intermediate text gets all of its next
+// steps in one go (rule engine where a rule exists, one LLM request for the
+// rest), so a `style × obstruction` cross costs one call per style key.
+async fn compose_record(
+    generation: &Generation,
+    rec: &mut Record,
+    compositions: &[Composition],
+    seed: u64,
+) -> Result<()> {
+    let todo: Vec<&Composition> = compositions
+        .iter()
+        .filter(|c| !rec.extra.contains_key(&c.key))
+        .collect();
+
+    // canonical prefix key -> text after those steps ("" = the original)
+    let mut texts: HashMap<String, String> =
+        HashMap::from([(String::new(), rec.instruction_original.clone())]);
+    // canonical prefix key -> noise of its last step, if that was a rule
+    let mut noise: HashMap<String, Value> = HashMap::new();
+    let mut last_err = None;
+
+    let depth = todo.iter().map(|c| c.steps.len()).max().unwrap_or(0);
+    for d in 0..depth {
+        let mut next: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for c in todo.iter().filter(|c| c.steps.len() > d) {
+            let keys = next.entry(c.prefix_key(d)).or_default();
+            if !keys.contains(&c.steps[d].key) {
+                keys.push(c.steps[d].key.clone());
+            }
+        }
+
+        for (parent, keys) in next {
+            // a failed earlier step leaves nothing to build on
+            let Some(text) = texts.get(&parent).cloned() else { continue };
+            let mut step = Record {
+                prompt_count: rec.prompt_count,
+                instruction_original: text,
+                extra: Map::new(),
+            };
+            let (rule_keys, llm_keys): (Vec<String>, Vec<String>) =
+                keys.iter().cloned().partition(|k| has_rule(k));
+            perturb_into(&mut step.extra, &step.instruction_original, rec.prompt_count, &rule_keys, seed, None);
+            if !llm_keys.is_empty() {
+                if let Err(err) = generate_record(generation, &mut step, &llm_keys, Task::Paraphrase).await {
+                    last_err = Some(err);
                 }
-                Err(err) => {
-                    last_error = Some(err);
-                    if attempt < cli.max_attempts {
+            }
+
+            for key in &keys {
+                let Some(Value::String(out)) = step.extra.get(key) else { continue };
+                let child = extend_key(&parent, key);
+                if let Some(meta) = step.extra.get(META_KEY).and_then(|m| m.get(key)) {
+                    noise.insert(child.clone(), meta.clone());
+                }
+                texts.insert(child, out.clone());
+            }
+        }
+    }
+
+    for c in &todo {
+        let Some(text) = texts.get(&c.key) else { continue };
+        rec.extra.insert(c.key.clone(), Value::String(text.clone()));
+
+        let steps: Vec<Value> = c
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let mut v = json!({
+                    "set": s.set,
+                    "key": s.key,
+                    "via": if has_rule(&s.key) { "rule" } else { "llm" },
+                });
+                if let Some(n) = noise.get(&c.prefix_key(i + 1)) {
+                    v["noise"] = n.clone();
+                }
+                v
+            })
+            .collect();
+        let slot = rec
+            .extra
+            .entry(COMPOSE_META_KEY)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(obj) = slot.as_object_mut() {
+            obj.insert(c.key.clone(), json!({ "steps": steps }));
+        }
+    }
+
+    let missing = todo.iter().filter(|c| !rec.extra.contains_key(&c.key)).count();
+    if missing == 0 {
+        return Ok(());
+    }
+    Err(last_err.unwrap_or_else(|| anyhow!("{missing} compositions incomplete")))
+}
+
+// Second pass: translate every generated source key back to English and
+// score the result against the original with chrF, so lost meaning can be
+// told apart from the effect of the language itself
+async fn backtranslate_record(generation: &Generation, rec: &mut Record, sources: &[String]) -> Result<()> {
+    // a source that failed to generate has nothing to translate back
+    let keys: Vec<String> = sources
+        .iter()
+        .filter(|k| matches!(rec.extra.get(*k), Some(Value::String(_))))
+        .map(|k| format!("{k}{BACKTRANSLATED_SUFFIX}"))
+        .collect();
+    let res = generate_record(generation, rec, &keys, Task::BackTranslate).await;
+
+    let mut scores = Map::new();
+    for src in sources {
+        let key = format!("{src}{BACKTRANSLATED_SUFFIX}");
+        if let Some(Value::String(text)) = rec.extra.get(&key) {
+            let score = chrf(&rec.instruction_original, text);
+            scores.insert(src.clone(), json!({ "backtranslated_key": key, "chrf": score }));
+        }
+    }
+    let slot = rec
+        .extra
+        .entry(BACKTRANSLATION_META_KEY)
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(obj) = slot.as_object_mut() {
+        obj.extend(scores);
+    }
+    res
+}
+
+// Take a rate-limit token unless the response cache answers the request
+async fn throttle(generation: &Generation, prompt: &str, schema: &Value) {
+    if !generation.provider.is_cached(prompt, schema) {
+        generation.limiter.acquire().await;
+    }
+}
+
+// One structured call for `keys`; accepted variants go straight into the
+// record. 429s pause every task and are retried here without using up an
+// attempt, up to MAX_RATE_LIMIT_RETRIES per record.
+async fn request_chunk(
+    generation: &Generation,
+    rec: &mut Record,
+    keys: &[String],
+    task: Task,
+    rate_limited: &mut u32,
+) -> Result<()> {
+    let many = task == Task::Paraphrase && generation.prompts.candidates > 1;
+    let (prompt, schema) = match task {
+        Task::Paraphrase => generation.prompts.paraphrase(&rec.instruction_original, keys),
+        Task::BackTranslate => (build_backtranslation_prompt(rec, keys), schema_for(keys)),
+    };
+
+    loop {
+        throttle(generation, &prompt, &schema).await;
+        let mut ver = match generation.provider.generate_json(&prompt, &schema).await {
+            Ok(ver) => ver,
+            Err(err) => {
+                if let Some(limited) = err.downcast_ref::<RateLimited>() {
+                    *rate_limited += 1;
+                    if *rate_limited <= MAX_RATE_LIMIT_RETRIES {
+                        let delay = limited.retry_after.unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF);
                         log::warn!(
-                            "prompt_count {} attempt {}/{} failed: {}",
-                            rec.prompt_count,
-                            attempt,
-                            cli.max_attempts,
-                            last_error.as_ref().unwrap()
-                        );
-                        sleep(Duration::from_millis(500 * u64::from(attempt))).await;
-                    } else {
-                        log::error!(
-                            "prompt_count {} failed after {} attempts – skipping.\n\
-                            Last error details:\n{}",
+                            "prompt_count {} rate limited ({}/{}), pausing all tasks for {:.1}s",
                             rec.prompt_count,
-                            cli.max_attempts,
-                            last_error.as_ref().unwrap()
+                            rate_limited,
+                            MAX_RATE_LIMIT_RETRIES,
+                            delay.as_secs_f64()
                         );
+                        generation.limiter.backoff(delay).await;
+                        continue;
                     }
                 }
+                return Err(err);
             }
-        }
+        };
 
-        bar.inc(1);
-        if !success {
-            continue;
+        let run_checks = generation.validate == ValidateMode::Retry;
+        if many {
+            select_candidates(generation, rec, keys, &mut ver, run_checks).await;
+        }
+        let mut rejected = Vec::new();
+        for key in keys {
+            let value = ver.remove(key);
+            match check_value(key, &rec.instruction_original, value.as_ref(), run_checks) {
+                Ok(()) => {
+                    rec.extra.insert(key.clone(), value.expect("checked present"));
+                }
+                Err(reason) => rejected.push(format!("{key}: {reason}")),
+            }
+        }
+        if rejected.is_empty() {
+            return Ok(());
+        }
+        if rejected.len() == keys.len() {
+            // a replayed answer would be rejected the same way on every retry
+            generation.provider.forget(&prompt, &schema);
         }
+        return Err(anyhow!(
+            "{} of {} requested keys missing or invalid ({})",
+            rejected.len(),
+            keys.len(),
+            rejected.join("; ")
+        ));
     }
-    bar.finish_with_message("done");
+}
 
-    log::info!("All records processed – writing output to {}", cli.output.display());
+// Replace each key's array of alternatives in `ver` by the best valid one
+// (or, if none is valid, the first, so the check reports why) and log every
+// candidate to the audit file
+async fn select_candidates(
+    generation: &Generation,
+    rec: &Record,
+    keys: &[String],
+    ver: &mut Map<String, Value>,
+    run_checks: bool,
+) {
+    let original = &rec.instruction_original;
+    let mut pools: Vec<(String, Vec<candidates::Candidate>)> = keys
+        .iter()
+        .map(|k| (k.clone(), candidates::collect(k, original, ver.remove(k).as_ref(), run_checks)))
+        .collect();
+
+    if generation.selection == Selection::Judge {
+        let contested: Vec<(String, Vec<String>)> = pools
+            .iter()
+            .map(|(k, c)| (k.clone(), c.iter().filter(|c| c.valid).map(|c| c.text.clone()).collect::<Vec<_>>()))
+            .filter(|(_, texts)| texts.len() > 1)
+            .collect();
+        if !contested.is_empty() {
+            let prompt = candidates::judge_prompt(original, &contested);
+            let schema = candidates::judge_schema(&contested);
+            throttle(generation, &prompt, &schema).await;
+            match generation.provider.generate_json(&prompt, &schema).await {
+                Ok(scores) => {
+                    for (k, pool) in pools.iter_mut() {
+                        candidates::apply_judge(pool, scores.get(k));
+                    }
+                }
+                Err(err) => log::warn!(
+                    "prompt_count {}: judge call failed, ranking candidates locally: {err}",
+                    rec.prompt_count
+                ),
+            }
+        }
+    }
 
-    // Write output
-    let out = serde_json::to_string_pretty(&records)?;
-    fs::write(&cli.output, out)?;
-    println!("output written to {}", cli.output.display());
-    log::info!("Output written to {}", cli.output.display());
+    for (key, pool) in pools {
+        let kept = candidates::best(&pool);
+        if let Some(c) = kept.or(if pool.is_empty() { None } else { Some(0) }).map(|i| &pool[i]) {
+            ver.insert(key.clone(), Value::String(c.text.clone()));
+        }
+        let Some(audit) = &generation.audit else { continue };
+        let choice = Choice {
+            prompt_count: rec.prompt_count,
+            key,
+            selection: format!("{:?}", generation.selection).to_lowercase(),
+            kept,
+            candidates: pool,
+        };
+        if let Err(e) = audit.lock().unwrap().append(&choice) {
+            log::warn!("candidates audit: {e:#}");
+        }
+    }
+}
 
+// Render the first-pass paraphrase requests of every record instead of
+// sending them: one `<prompt_count>_<chunk>.txt` per call. With --compose
+// that is the first step of every composition that goes to the model; later
+// steps and back-translations depend on model output and are not rendered.
+fn dry_run(
+    dir: &Path,
+    prompts: &Prompts,
+    records: &[Record],
+    keys: &[String],
+    scrub: Option<(&Scrubber, &str)>,
+) -> Result<()> {
+    let out = DryRun::new(dir)?;
+    for rec in records {
+        let chunks = prompts.chunks(keys, &rec.instruction_original, Task::Paraphrase);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let (mut prompt, schema) = prompts.paraphrase(&rec.instruction_original, chunk);
+            if let Some((scrubber, set)) = scrub {
+                prompt = scrubber.scrub(set, &prompt);
+            }
+            out.write(&format!("{}_{}", rec.prompt_count, i + 1), &prompt, &schema)?;
+        }
+    }
+    println!("dry run: {}", out.summary());
+    log::info!("Dry run: {}", out.summary());
     Ok(())
 }
 
-fn build_client() -> Result<reqwest::Client> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-    let client = reqwest::Client::builder()
-        .default_headers(headers)
-        .timeout(Duration::from_secs(90))
-        .build()?;
+// Write the scrub map and report the redactions per set
+fn finish_scrub(scrubber: Option<&Scrubber>) -> Result<()> {
+    let Some(scrubber) = scrubber else { return Ok(()) };
+    scrubber.save()?;
+    let lines = scrubber.summary();
+    if lines.is_empty() {
+        println!("scrub: nothing redacted");
+        log::info!("Scrub: nothing redacted");
+    }
+    for line in lines {
+        println!("scrub: {line}");
+        log::info!("Scrub: {line}");
+    }
+    println!("scrub map written to {}", scrubber.map_path().display());
+    Ok(())
+}
 
-    Ok(client)
+// Raw input object -> Record, renaming the profile's source field
+fn to_record(profile: &Profile, mut obj: serde_json::Map<String, serde_json::Value>) -> Result<Record> {
+    let prompt_count = obj
+        .remove("prompt_count")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+        .context("missing or invalid prompt_count")?;
+    let (instruction_original, extra) = profile.take_source(obj)?;
+    Ok(Record { prompt_count, instruction_original, extra })
 }
 
-// Build the generation prompt
-fn build_prompt(original: &str, keys: &[&str], label: &str) -> String {
+// Build the generation prompt; `rules` is the profile's block (may be empty)
+fn build_prompt(
+    original: &str,
+    keys: &[String],
+    label: &str,
+    rules: &str,
+    taxonomy: &Taxonomy,
+    candidates: usize,
+) -> String {
     let bullet_list = keys
         .iter()
-        .map(|k| format!("* **{k}** – rewrite in the \"{label}\" variant ({k})."))
+        .map(|k| match taxonomy.description(k) {
+            Some(desc) => format!("* **{k}** – {desc}."),
+            None => format!("* **{k}** – rewrite in the \"{label}\" variant ({k})."),
+        })
         .collect::<Vec<_>>()
         .join("\n");
+    let shape = if candidates > 1 {
+        format!(", each holding an array of {candidates} clearly different alternatives")
+    } else {
+        String::new()
+    };
+    let mut dims: Vec<&str> = keys.iter().filter_map(|k| taxonomy.scale_of(k)).map(|(d, _, _)| d).collect();
+    dims.dedup();
+    let scale_note = if dims.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Keys ending in `_L<n>` are levels of a graded scale ({}): vary only that dimension, \
+             keep everything else identical across the levels, and space the levels evenly so \
+             that each is clearly stronger than the one before.\n",
+            dims.join(", ")
+        )
+    };
 
     format!(
         "You are an expert paraphraser.\n\
@@ -550,17 +1192,41 @@ fn build_prompt(original: &str, keys: &[&str], label: &str) -> String {
          {bullet_list}\n\n\
          Rewrite the original instruction in the style of each key name.\n\
          Phrase every variant instruction so that its answer will be an answer to the original instruction or in some way (e.g. completeness, creativity, style, structure, efficiency, tone) better.\n\
-         **Important:** Each variant must still yield an answer to the _original instruction_.\n\n\
-         Return **only** one JSON object with exactly those keys.\n\n\
+         **Important:** Each variant must still yield an answer to the _original instruction_.\n\
+         {scale_note}\n\
+         {rules}\
+         Return **only** one JSON object with exactly those keys{shape}.\n\n\
          Original Instruction:\n{original}"
     )
 }
 
+// Back-translation prompt: one block per `<key>__backtranslated`, holding the
+// text generated for `<key>`
+fn build_backtranslation_prompt(rec: &Record, keys: &[String]) -> String {
+    let blocks = keys
+        .iter()
+        .map(|k| {
+            let source = k.strip_suffix(BACKTRANSLATED_SUFFIX).unwrap_or(k);
+            let text = rec.extra.get(source).and_then(Value::as_str).unwrap_or_default();
+            format!("* **{k}**:\n{text}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        "You are a professional translator.\n\
+         Translate each text below back into English, as literally as fluent English allows.\n\
+         Do not correct, improve, summarise or answer it; keep its form (question, list, request).\n\n\
+         {blocks}\n\n\
+         Return **only** one JSON object with exactly those keys, each holding the English translation."
+    )
+}
+
 // JSON Schema that constrains Gemini's output
-fn schema_for(keys: &[&str]) -> serde_json::Value {
+fn schema_for(keys: &[String]) -> serde_json::Value {
     let mut props = serde_json::Map::new();
     for k in keys {
-        props.insert((*k).into(), json!({ "type": "string" }));
+        props.insert(k.clone(), json!({ "type": "string" }));
     }
     json!({
         "type": "object",
@@ -568,56 +1234,3 @@ fn schema_for(keys: &[&str]) -> serde_json::Value {
         "required": keys,
     })
 }
-
-async fn query_gemini(
-    client: &reqwest::Client,
-    key: &str,
-    schema: &serde_json::Value,
-    prompt: &str,
-    model: &str,
-) -> Result<serde_json::Map<String, serde_json::Value>> {
-    let url = format!(
-        "{ENDPOINT}/models/{model}:generateContent?key={key}",
-        ENDPOINT = ENDPOINT,
-        model  = model,
-        key    = key
-    );
-
-    let body = json!({
-        "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
-        "generationConfig": {
-            "responseMimeType": "application/json",
-            "responseSchema":  schema
-        }
-    });
-
-    let resp = client.post(&url).json(&body).send().await?;
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let msg    = resp.text().await?;
-        return Err(anyhow!("{} — {}", status, msg));
-    }
-
-    let resp_json: serde_json::Value = resp.json().await?;
-
-    // Gracefully report any layout surprises with the full payload
-    let json_text = resp_json["candidates"][0]["content"]["parts"][0]["text"]
-        .as_str()
-        .ok_or_else(|| {
-            anyhow!(
-                "unexpected response structure; full JSON from Gemini:\n{}",
-                serde_json::to_string_pretty(&resp_json)
-                    .unwrap_or_else(|_| "<unable to serialise>".to_string())
-            )
-        })?;
-
-    // Attach the offending string if the inner deserialise blows up
-    let map: serde_json::Map<String, serde_json::Value> =
-        serde_json::from_str(json_text).map_err(|e| {
-            anyhow!(
-                "failed to parse JSON returned by Gemini: {e}\njson_text:\n{json_text}"
-            )
-        })?;
-
-    Ok(map)
-}
@@ -0,0 +1,309 @@
+/*
+cargo import_review \
+    a_data/alpaca/prxed/all.json \
+    a_data/alpaca/prxed/review_all_anna.tsv \
+    a_data/alpaca/prxed/review_all_ben.tsv \
+    -o a_data/alpaca/prxed/all_reviewed.json
+
+# report only, the paraphrase file is not rewritten
+cargo import_review --dry-run a_data/alpaca/prxed/all.json /tmp/review.csv
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indexmap::IndexMap;
+use rephras::review::{cohen_kappa, delimiter, Row, Verdict, REJECTED_PREFIX};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+// Apply filled-in review sheets to a paraphrase JSON and report agreement.
+// With several sheets (one per annotator) an item is applied only when a
+// strict majority of its verdicts agree.
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    input: PathBuf,
+
+    // Review sheets written by `export_review` and filled in by annotators
+    #[arg(required = true)]
+    reviews: Vec<PathBuf>,
+
+    // Reviewed paraphrase JSON (default: `<input>_reviewed.json`)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    // Agreement report (default: `<output>.review.json`)
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    // Only write the report
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Vote {
+    verdict: Verdict,
+    corrected: String,
+}
+
+// Everything the sheets say about one (prompt_count, key)
+#[derive(Debug)]
+struct Item {
+    set: String,
+    auto_ok: bool,
+    // one slot per sheet, `None` where that annotator left it empty
+    votes: Vec<Option<Vote>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Tally {
+    ok: usize,
+    fix: usize,
+    reject: usize,
+    conflict: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct PairAgreement {
+    a: String,
+    b: String,
+    items: usize,
+    percent: f64,
+    kappa: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    input: String,
+    output: Option<String>,
+    reviews: Vec<String>,
+    items: usize,
+    reviewed: usize,
+    applied: Tally,
+    by_set: BTreeMap<String, Tally>,
+    // pairwise between annotators, on items both reviewed
+    annotators: Vec<PairAgreement>,
+    // final human verdict (ok vs fix/reject) against the export-time validators
+    auto_check: Option<PairAgreement>,
+    conflicts: Vec<String>,
+    unmatched: Vec<String>,
+    problems: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let output = cli.output.clone().unwrap_or_else(|| {
+        let stem = cli.input.file_stem().unwrap_or_default().to_string_lossy();
+        cli.input.with_file_name(format!("{stem}_reviewed.json"))
+    });
+    let report_path = cli
+        .report
+        .clone()
+        .unwrap_or_else(|| output.with_extension("review.json"));
+
+    let data = fs::read_to_string(&cli.input)
+        .with_context(|| format!("failed to read {}", cli.input.display()))?;
+    // records stay plain objects, so only the reviewed keys change on the way
+    // through (an `instruction` field keeps its name, fields keep their order)
+    let mut records: Vec<IndexMap<String, Value>> = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a paraphrase JSON array", cli.input.display()))?;
+
+    let mut report = Report {
+        input: cli.input.display().to_string(),
+        output: (!cli.dry_run).then(|| output.display().to_string()),
+        reviews: cli.reviews.iter().map(|p| p.display().to_string()).collect(),
+        ..Report::default()
+    };
+
+    // (prompt_count, key) -> verdicts of every sheet
+    let mut items: BTreeMap<(u32, String), Item> = BTreeMap::new();
+    for (i, path) in cli.reviews.iter().enumerate() {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter(path))
+            .from_path(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        for (line, row) in reader.deserialize::<Row>().enumerate() {
+            // header is line 1
+            let at = format!("{}:{}", path.display(), line + 2);
+            let row = row.with_context(|| format!("{at}: malformed row"))?;
+            let verdict = Verdict::parse(&row.verdict).with_context(|| at.clone())?;
+            let item = items.entry((row.prompt_count, row.key.clone())).or_insert_with(|| Item {
+                set: row.set.clone(),
+                auto_ok: row.auto_check == "ok",
+                votes: vec![None; cli.reviews.len()],
+            });
+            item.votes[i] = verdict.map(|verdict| Vote { verdict, corrected: row.corrected.trim().to_string() });
+        }
+    }
+    report.items = items.len();
+    if items.is_empty() {
+        bail!("the review sheets hold no rows");
+    }
+
+    let mut index: HashMap<u32, usize> = HashMap::with_capacity(records.len());
+    for (i, rec) in records.iter().enumerate() {
+        let Some(prompt_count) = rec.get("prompt_count").and_then(Value::as_u64).and_then(|n| u32::try_from(n).ok())
+        else {
+            bail!("{}: record {} has no numeric prompt_count", cli.input.display(), i + 1);
+        };
+        index.insert(prompt_count, i);
+    }
+    let mut human_vs_auto: Vec<(bool, bool)> = Vec::new();
+
+    for ((prompt_count, key), item) in &items {
+        let votes: Vec<&Vote> = item.votes.iter().flatten().collect();
+        if votes.is_empty() {
+            continue;
+        }
+        report.reviewed += 1;
+        let id = format!("{prompt_count} {key}");
+        let tally = report.by_set.entry(item.set.clone()).or_default();
+
+        let Some(verdict) = majority(&votes) else {
+            let all: Vec<String> = votes.iter().map(|v| format!("{:?}", v.verdict).to_lowercase()).collect();
+            report.conflicts.push(format!("{id}: {}", all.join(" / ")));
+            tally.conflict += 1;
+            report.applied.conflict += 1;
+            continue;
+        };
+        human_vs_auto.push((verdict == Verdict::Ok, item.auto_ok));
+
+        let Some(rec) = index.get(prompt_count).map(|&i| &mut records[i]) else {
+            report.unmatched.push(format!("{id}: no record with this prompt_count"));
+            continue;
+        };
+        let Some(text) = rec.get(key).and_then(Value::as_str).map(str::to_string) else {
+            report.unmatched.push(format!("{id}: key not in the record"));
+            continue;
+        };
+
+        match verdict {
+            Verdict::Ok => {
+                tally.ok += 1;
+                report.applied.ok += 1;
+            }
+            Verdict::Fix => {
+                let mut corrections: Vec<&str> = votes
+                    .iter()
+                    .filter(|v| v.verdict == Verdict::Fix && !v.corrected.is_empty())
+                    .map(|v| v.corrected.as_str())
+                    .collect();
+                corrections.sort_unstable();
+                corrections.dedup();
+                let corrected = match corrections.as_slice() {
+                    [] => {
+                        report.problems.push(format!("{id}: verdict fix without corrected text"));
+                        continue;
+                    }
+                    [one] => one.to_string(),
+                    // agreeing on `fix` but not on the fix is still a conflict
+                    differing => {
+                        let all: Vec<String> = differing.iter().map(|c| format!("{c:?}")).collect();
+                        report.conflicts.push(format!("{id}: fix, corrections differ: {}", all.join(" / ")));
+                        tally.conflict += 1;
+                        report.applied.conflict += 1;
+                        continue;
+                    }
+                };
+                rec.insert(key.clone(), Value::String(corrected));
+                tally.fix += 1;
+                report.applied.fix += 1;
+            }
+            Verdict::Reject => {
+                // the rejected text takes the place of the key
+                if let Some((at, _, _)) = rec.shift_remove_full(key) {
+                    rec.shift_insert(at, format!("{REJECTED_PREFIX}{key}"), Value::String(text));
+                }
+                tally.reject += 1;
+                report.applied.reject += 1;
+            }
+        }
+    }
+
+    // pairwise agreement between annotators on the items both reviewed
+    for a in 0..cli.reviews.len() {
+        for b in a + 1..cli.reviews.len() {
+            let pairs: Vec<(Verdict, Verdict)> = items
+                .values()
+                .filter_map(|it| Some((it.votes[a].as_ref()?.verdict, it.votes[b].as_ref()?.verdict)))
+                .collect();
+            report.annotators.push(agreement(&report.reviews[a], &report.reviews[b], &pairs));
+        }
+    }
+    if !human_vs_auto.is_empty() {
+        report.auto_check = Some(agreement("human", "auto_check", &human_vs_auto));
+    }
+
+    if !cli.dry_run {
+        fs::write(&output, serde_json::to_string_pretty(&records)?)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+    }
+    fs::write(&report_path, serde_json::to_string_pretty(&report)?)
+        .with_context(|| format!("failed to write {}", report_path.display()))?;
+
+    print_summary(&report);
+    if !cli.dry_run {
+        println!("reviewed paraphrases written to {}", output.display());
+    }
+    println!("report written to {}", report_path.display());
+    Ok(())
+}
+
+// Verdict of a strict majority of the votes, if there is one
+fn majority(votes: &[&Vote]) -> Option<Verdict> {
+    let mut counts: BTreeMap<Verdict, usize> = BTreeMap::new();
+    for v in votes {
+        *counts.entry(v.verdict).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .find(|(_, n)| 2 * n > votes.len())
+        .map(|(verdict, _)| verdict)
+}
+
+fn agreement<T: Eq + std::hash::Hash + Copy>(a: &str, b: &str, pairs: &[(T, T)]) -> PairAgreement {
+    let same = pairs.iter().filter(|(x, y)| x == y).count();
+    PairAgreement {
+        a: a.to_string(),
+        b: b.to_string(),
+        items: pairs.len(),
+        percent: if pairs.is_empty() { 0.0 } else { 100.0 * same as f64 / pairs.len() as f64 },
+        kappa: cohen_kappa(pairs),
+    }
+}
+
+fn print_summary(report: &Report) {
+    println!("{} items in the sheets, {} reviewed", report.items, report.reviewed);
+    println!(
+        "{:24} {:>6} {:>6} {:>6} {:>9}",
+        "set", "ok", "fix", "reject", "conflict"
+    );
+    for (set, t) in &report.by_set {
+        println!("{set:24} {:>6} {:>6} {:>6} {:>9}", t.ok, t.fix, t.reject, t.conflict);
+    }
+    let t = &report.applied;
+    println!("{:24} {:>6} {:>6} {:>6} {:>9}", "TOTAL", t.ok, t.fix, t.reject, t.conflict);
+
+    for p in report.annotators.iter().chain(&report.auto_check) {
+        let kappa = p.kappa.map_or("-".to_string(), |k| format!("{k:.3}"));
+        println!(
+            "agreement {} vs {}: {:.1}% on {} items, kappa {kappa}",
+            p.a, p.b, p.percent, p.items
+        );
+    }
+    for (label, list) in [
+        ("conflicting verdicts", &report.conflicts),
+        ("unmatched rows", &report.unmatched),
+        ("problems", &report.problems),
+    ] {
+        if !list.is_empty() {
+            println!("{} {label} (see report)", list.len());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/*
+cargo perturb \
+    --version-set obstruction \
+    --seed 7 \
+    a_data/alpaca/slice_100/alpaca_slice1.json \
+    a_data/alpaca/slice_100/alpaca_prx_obstruction_rules_slice1.json
+
+# dose-response: same records, same seed, increasing noise
+for p in 0.1 0.3 0.5; do
+  cargo perturb --keys instruct_typo_swap,instruct_zero_width --intensity $p \
+      b_tests/phrx/alpaca_slice1.json /tmp/alpaca_noise_$p.json
+done
+
+cargo perturb --list
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rephras::perturb::{default_intensity, has_rule, intensity_unit, perturb_into, RULE_KEYS};
+use rephras::taxonomy::{Taxonomy, DEFAULT_TAXONOMY};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+// Alpaca-style record with a flexible extra map to hold any new keys
+#[derive(Debug, Deserialize, Serialize)]
+struct Record {
+    prompt_count: u32,
+    #[serde(alias = "instruction", alias = "instruction_original")]
+    instruction_original: String,
+
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+// Fill the mechanical paraphrase keys locally with seeded, rule-based noise
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    #[arg(required_unless_present = "list")]
+    input: Option<PathBuf>,
+    #[arg(required_unless_present = "list")]
+    output: Option<PathBuf>,
+
+    // Key-set whose rule-backed keys are filled (keys without a rule are skipped)
+    #[arg(long, default_value = "obstruction")]
+    version_set: String,
+
+    // Explicit comma-separated keys instead of a set
+    #[arg(long, value_delimiter = ',')]
+    keys: Vec<String>,
+
+    #[arg(long, default_value = DEFAULT_TAXONOMY)]
+    taxonomy: PathBuf,
+
+    // Base seed; each (record, key) pair derives its own from it
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    // Noise intensity in [0, 1] for every key (default: per-rule default)
+    #[arg(long)]
+    intensity: Option<f64>,
+
+    // Print the available rules and exit
+    #[arg(long)]
+    list: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if cli.list {
+        for key in RULE_KEYS {
+            println!("{key:26} default {:.2}  {}", default_intensity(key), intensity_unit(key));
+        }
+        return Ok(());
+    }
+    let (Some(input), Some(output)) = (&cli.input, &cli.output) else {
+        unreachable!("clap requires input and output without --list");
+    };
+    if let Some(p) = cli.intensity {
+        if !(0.0..=1.0).contains(&p) {
+            bail!("--intensity must be within [0, 1], got {p}");
+        }
+    }
+
+    let keys: Vec<String> = if cli.keys.is_empty() {
+        let taxonomy = Taxonomy::load(&cli.taxonomy)?;
+        let (_, set) = taxonomy.resolve_set(&cli.version_set)?;
+        set.keys.iter().filter(|k| has_rule(k)).cloned().collect()
+    } else {
+        if let Some(bad) = cli.keys.iter().find(|k| !has_rule(k)) {
+            bail!("no local rule for {bad} (see --list)");
+        }
+        cli.keys.clone()
+    };
+    if keys.is_empty() {
+        bail!("set {} has no rule-backed keys (see --list)", cli.version_set);
+    }
+
+    let data = fs::read_to_string(input)
+        .with_context(|| format!("failed to read {}", input.display()))?;
+    let mut records: Vec<Record> = serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse {}", input.display()))?;
+
+    for rec in &mut records {
+        perturb_into(
+            &mut rec.extra,
+            &rec.instruction_original,
+            rec.prompt_count,
+            &keys,
+            cli.seed,
+            cli.intensity,
+        );
+    }
+
+    fs::write(output, serde_json::to_string_pretty(&records)?)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    println!(
+        "{} records × {} keys written to {} (seed {})",
+        records.len(),
+        keys.len(),
+        output.display(),
+        cli.seed
+    );
+    Ok(())
+}
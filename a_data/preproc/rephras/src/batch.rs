@@ -0,0 +1,57 @@
+// Splitting a version set's keys over several generation calls.
+//
+// Large sets such as `extra` (60+ keys, some of them long
+// `instruct_condensed_then_expand_with_...` chains) overflow the model's
+// output limit in a single structured response and come back as truncated
+// JSON. Keys are grouped greedily, in set order, so that each call stays
+// under an estimated output-token budget and/or a fixed key count.
+
+// Default budget per call; well below the 8k output cap of the Gemini models
+pub const DEFAULT_MAX_OUTPUT_TOKENS: usize = 4000;
+
+// Per-key JSON overhead: quotes, colon, comma, escaping slack
+const JSON_OVERHEAD: usize = 8;
+
+// Rough output-token estimate for one key's variant. Words joined by
+// `_and_` / `_with_` / `_then_` each ask for another component on top of
+// the rewrite, and `expand` makes the whole thing longer still.
+pub fn estimate_tokens(key: &str, original: &str) -> usize {
+    let base = original.chars().count().div_ceil(4).max(16);
+    let components = 1
+        + key.matches("_and_").count()
+        + key.matches("_with_").count()
+        + key.matches("_then_").count();
+    let expand = if key.contains("expand") || key.contains("elaborat") { 2 } else { 1 };
+
+    key.len().div_ceil(4) + base * components * expand + JSON_OVERHEAD
+}
+
+// Group `keys` into calls. `keys_per_call == 0` and `max_tokens == 0` each
+// disable that limit; every chunk holds at least one key, so a single key
+// above the budget still gets its own call.
+pub fn chunk_keys(
+    keys: &[String],
+    original: &str,
+    keys_per_call: usize,
+    max_tokens: usize,
+) -> Vec<Vec<String>> {
+    let mut chunks: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut tokens = 0;
+
+    for key in keys {
+        let cost = estimate_tokens(key, original);
+        let full = (keys_per_call > 0 && current.len() >= keys_per_call)
+            || (max_tokens > 0 && tokens + cost > max_tokens);
+        if full && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            tokens = 0;
+        }
+        current.push(key.clone());
+        tokens += cost;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
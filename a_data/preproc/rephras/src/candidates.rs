@@ -0,0 +1,145 @@
+// Several candidates per paraphrase key, keeping the most faithful one.
+//
+// With `--candidates N` the schema asks for an array of N alternatives per
+// key instead of a single string. Candidates failing the key's validator are
+// dropped; the rest are ranked by chrF against the original (`local`) or by
+// an equivalence-judge call scoring each 0-5, chrF breaking ties (`judge`).
+// Every candidate, kept or not, goes to `<output>.candidates.jsonl` for audit.
+
+use crate::similarity::chrf;
+use crate::validate::check_value;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Selection {
+    // validators plus surface similarity to the original, no extra call
+    Local,
+    // one equivalence-judge call per request, scores 0-5
+    Judge,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub text: String,
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub chrf: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge: Option<u64>,
+}
+
+// One audit line: every candidate of one key and which one was kept
+#[derive(Debug, Serialize)]
+pub struct Choice {
+    pub prompt_count: u32,
+    pub key: String,
+    pub selection: String,
+    pub kept: Option<usize>,
+    pub candidates: Vec<Candidate>,
+}
+
+// Schema asking for `n` alternatives per key
+pub fn schema_for(keys: &[String], n: usize) -> Value {
+    let mut props = Map::new();
+    for k in keys {
+        props.insert(
+            k.clone(),
+            json!({ "type": "array", "items": { "type": "string" }, "minItems": n, "maxItems": n }),
+        );
+    }
+    json!({ "type": "object", "properties": props, "required": keys })
+}
+
+// Validate and score the alternatives returned for `key`
+pub fn collect(key: &str, original: &str, value: Option<&Value>, run_checks: bool) -> Vec<Candidate> {
+    let items: Vec<Value> = match value {
+        Some(Value::Array(items)) => items.clone(),
+        // a model ignoring the array schema still gives one usable candidate
+        Some(v @ Value::String(_)) => vec![v.clone()],
+        _ => Vec::new(),
+    };
+    items
+        .iter()
+        .map(|item| {
+            let check = check_value(key, original, Some(item), run_checks);
+            let text = item.as_str().unwrap_or_default().to_string();
+            Candidate {
+                chrf: chrf(original, &text),
+                text,
+                valid: check.is_ok(),
+                reason: check.err(),
+                judge: None,
+            }
+        })
+        .collect()
+}
+
+// Best valid candidate: highest judge score, then highest chrF, then first
+pub fn best(candidates: &[Candidate]) -> Option<usize> {
+    candidates
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, c)| c.valid)
+        .max_by(|(_, a), (_, b)| a.judge.cmp(&b.judge).then(a.chrf.total_cmp(&b.chrf)))
+        .map(|(i, _)| i)
+}
+
+// Judge prompt over the valid candidates of every key (`(key, texts)`)
+pub fn judge_prompt(original: &str, keys: &[(String, Vec<String>)]) -> String {
+    let blocks = keys
+        .iter()
+        .map(|(k, texts)| {
+            let numbered: Vec<String> =
+                texts.iter().enumerate().map(|(i, t)| format!("{}. {t}", i + 1)).collect();
+            format!("* **{k}**:\n{}", numbered.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        "You are an expert in linguistic semantics.\n\
+         Compare every candidate paraphrase below with the *Original Instruction*. Judge only \
+         whether it asks for the same information or action; ignore style, tone, politeness, \
+         wording, language and noise such as typos or odd characters.\n\
+         Score each candidate independently with an integer from 0 to 5:\n\
+         - 5: asks for exactly the same thing\n\
+         - 4: the same request with a very small added constraint\n\
+         - 3: clearly adds a requirement or drops a key part\n\
+         - 2: the core task is substantially different\n\
+         - 1: the same broad topic, a different request\n\
+         - 0: nonsensical or unrelated\n\n\
+         {blocks}\n\n\
+         Return **only** one JSON object with exactly those keys, each holding an array with one \
+         score per candidate, in the order given.\n\n\
+         Original Instruction:\n{original}"
+    )
+}
+
+pub fn judge_schema(keys: &[(String, Vec<String>)]) -> Value {
+    let mut props = Map::new();
+    for (k, texts) in keys {
+        props.insert(
+            k.clone(),
+            json!({
+                "type": "array",
+                "items": { "type": "integer", "minimum": 0, "maximum": 5 },
+                "minItems": texts.len(),
+                "maxItems": texts.len()
+            }),
+        );
+    }
+    let names: Vec<&String> = keys.iter().map(|(k, _)| k).collect();
+    json!({ "type": "object", "properties": props, "required": names })
+}
+
+// Attach the judge's scores, in order, to the valid candidates of `key`
+pub fn apply_judge(candidates: &mut [Candidate], scores: Option<&Value>) {
+    let Some(Value::Array(scores)) = scores else { return };
+    for (c, s) in candidates.iter_mut().filter(|c| c.valid).zip(scores) {
+        c.judge = s.as_u64();
+    }
+}
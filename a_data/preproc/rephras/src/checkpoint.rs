@@ -0,0 +1,171 @@
+// Crash-safe progress for long generation runs.
+//
+// Every finished record is appended as one JSON line to
+// `<output>.journal.jsonl` and flushed straight away, so a killed PBS/tmux
+// job only loses the records that were in flight. Records that exhausted
+// their attempts are listed in `<output>.failures.json` together with the keys
+// they still lack; `--resume` re-requests only those keys.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+// One record whose keys could not all be generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub prompt_count: u32,
+    pub version_set: String,
+    pub model: String,
+    pub attempts: u8,
+    // keys still absent from the record; the others were kept
+    #[serde(default)]
+    pub missing_keys: Vec<String>,
+    pub error: String,
+    pub failed_at: String,
+}
+
+impl Failure {
+    pub fn new(
+        prompt_count: u32,
+        version_set: &str,
+        model: &str,
+        attempts: u8,
+        missing_keys: Vec<String>,
+        error: String,
+    ) -> Self {
+        Self {
+            prompt_count,
+            version_set: version_set.to_string(),
+            model: model.to_string(),
+            attempts,
+            missing_keys,
+            error,
+            failed_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+// `out.json` -> `out.json.journal.jsonl`
+pub fn journal_path(output: &Path) -> PathBuf {
+    with_suffix(output, "journal.jsonl")
+}
+
+// `out.json` -> `out.json.failures.json`
+pub fn failures_path(output: &Path) -> PathBuf {
+    with_suffix(output, "failures.json")
+}
+
+// `out.json` -> `out.json.validation.json`
+pub fn validation_path(output: &Path) -> PathBuf {
+    with_suffix(output, "validation.json")
+}
+
+// `out.json` -> `out.json.candidates.jsonl`
+pub fn candidates_path(output: &Path) -> PathBuf {
+    with_suffix(output, "candidates.jsonl")
+}
+
+fn with_suffix(output: &Path, suffix: &str) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Append-only JSONL writer
+pub struct Journal {
+    path: PathBuf,
+    writer: BufWriter<fs::File>,
+}
+
+impl Journal {
+    // `resume == false` starts a fresh journal, `true` keeps appending
+    pub fn open(path: &Path, resume: bool) -> Result<Self> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .read(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(path)
+            .with_context(|| format!("failed to open journal {}", path.display()))?;
+        if resume {
+            // appending after a torn last line would glue the next record onto
+            // it and turn it into corruption in the middle of the file
+            let len = file.metadata()?.len();
+            let keep = complete_len(&mut file, len)?;
+            if keep < len {
+                log::warn!("dropping torn last line ({} bytes) of {}", len - keep, path.display());
+                file.set_len(keep)
+                    .with_context(|| format!("failed to truncate journal {}", path.display()))?;
+            }
+        }
+        Ok(Self { path: path.to_path_buf(), writer: BufWriter::new(file) })
+    }
+
+    pub fn append<T: Serialize>(&mut self, item: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, item)?;
+        self.writer.write_all(b"\n")?;
+        // flush per line: a crash must never leave a finished record in the buffer
+        self.writer.flush()
+            .with_context(|| format!("failed to flush journal {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+// Length of `file` up to and including its last newline
+fn complete_len(file: &mut fs::File, len: u64) -> Result<u64> {
+    let mut end = len;
+    let mut buf = vec![0u8; 64 * 1024];
+    while end > 0 {
+        let start = end.saturating_sub(buf.len() as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(i) = chunk.iter().rposition(|&b| b == b'\n') {
+            return Ok(start + i as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+// Read every complete line of a journal. A torn last line (process killed
+// mid-write) is skipped with a warning; corruption anywhere else is an error.
+pub fn read_journal<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to open journal {}", path.display()))?;
+    let lines: Vec<String> = BufReader::new(file).lines().collect::<Result<_, _>>()?;
+
+    let mut items = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(item) => items.push(item),
+            Err(e) if i + 1 == lines.len() => {
+                log::warn!("ignoring torn last line {} of {}: {e}", i + 1, path.display());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("corrupt line {} in journal {}", i + 1, path.display())
+                });
+            }
+        }
+    }
+    Ok(items)
+}
+
+pub fn write_failures(path: &Path, failures: &[Failure]) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(failures)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
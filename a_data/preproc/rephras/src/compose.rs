@@ -0,0 +1,116 @@
+// Compositional paraphrases: several base transforms applied in sequence.
+//
+// Hand-enumerated combos such as `instruct_emoji_and_typo_and_missing_bracket`
+// cannot separate the contribution of each part. A composition names its
+// steps explicitly (`style:instruct_formal_academic,obstruction:instruct_typo_swap`)
+// and is stored under the canonical key `instruct_formal_academic__typo_swap`:
+// the first key in full, every following key without its `instruct_` prefix,
+// joined by `__`. Provenance goes under `COMPOSE_META_KEY`.
+
+use crate::taxonomy::Taxonomy;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+
+// Top-level record key holding `{composed_key: {steps: [...]}}`
+pub const COMPOSE_META_KEY: &str = "compositions";
+
+// Separator between the steps of a canonical key
+pub const STEP_SEP: &str = "__";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Step {
+    pub set: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Composition {
+    // canonical `a__x__b` key the result is stored under
+    pub key: String,
+    pub steps: Vec<Step>,
+}
+
+impl Composition {
+    fn new(steps: Vec<Step>) -> Self {
+        Self { key: canonical_key(&steps), steps }
+    }
+
+    // Canonical key of the first `n` steps (the intermediate text they produce)
+    pub fn prefix_key(&self, n: usize) -> String {
+        canonical_key(&self.steps[..n])
+    }
+}
+
+pub fn canonical_key(steps: &[Step]) -> String {
+    steps.iter().fold(String::new(), |acc, s| extend_key(&acc, &s.key))
+}
+
+// Canonical key after applying `key` on top of `parent` ("" = the original)
+pub fn extend_key(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}{STEP_SEP}{}", key.trim_start_matches("instruct_"))
+    }
+}
+
+// `style:instruct_formal_academic,obstruction:instruct_typo_swap`; the set may be
+// left out (`instruct_typo_swap`) if the key belongs to exactly one set
+pub fn parse_spec(spec: &str, taxonomy: &Taxonomy) -> Result<Composition> {
+    let mut steps = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let step = match part.split_once(':') {
+            Some((set, key)) => {
+                let (canonical, ks) = taxonomy.resolve_set(set)?;
+                if !ks.keys.iter().any(|k| k == key) {
+                    bail!("{key} is not in set {canonical}");
+                }
+                Step { set: canonical.to_string(), key: key.to_string() }
+            }
+            None => {
+                let owners: Vec<&String> = taxonomy
+                    .sets
+                    .iter()
+                    .filter(|(_, s)| s.keys.iter().any(|k| k == part))
+                    .map(|(name, _)| name)
+                    .collect();
+                match owners.as_slice() {
+                    [one] => Step { set: one.to_string(), key: part.to_string() },
+                    [] => bail!("{part} is not in any set"),
+                    many => bail!(
+                        "{part} is in several sets ({}) – write it as set:key",
+                        many.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")
+                    ),
+                }
+            }
+        };
+        steps.push(step);
+    }
+    if steps.len() < 2 {
+        bail!("composition {spec:?} needs at least two steps");
+    }
+    Ok(Composition::new(steps))
+}
+
+// Every key of `first` followed by every key of `second`
+pub fn cross(taxonomy: &Taxonomy, first: &str, second: &str) -> Result<Vec<Composition>> {
+    let (a_name, a) = taxonomy.resolve_set(first)?;
+    let (b_name, b) = taxonomy.resolve_set(second)?;
+    let mut out = Vec::with_capacity(a.keys.len() * b.keys.len());
+    for ka in &a.keys {
+        for kb in &b.keys {
+            out.push(Composition::new(vec![
+                Step { set: a_name.to_string(), key: ka.clone() },
+                Step { set: b_name.to_string(), key: kb.clone() },
+            ]));
+        }
+    }
+    Ok(out)
+}
+
+// Drop repeated compositions, keeping the first
+pub fn dedup(compositions: Vec<Composition>) -> Vec<Composition> {
+    let mut seen = HashSet::new();
+    compositions.into_iter().filter(|c| seen.insert(c.key.clone())).collect()
+}
@@ -0,0 +1,130 @@
+// Regex field extraction driven by a rules file (see a_data/extract_rules/).
+//
+// Each rule applies one pattern to one string field and writes every named
+// capture group `(?P<field>...)` that took part in the match to a field of
+// that name. Rules run in file order, so a later rule can read a field an
+// earlier one wrote. Captures are copied verbatim unless the rule sets
+// `trim = true`.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default = "default_source")]
+    pub source: String,
+    pub pattern: String,
+    // every match, one array per group, instead of the first match
+    #[serde(default)]
+    pub multiple: bool,
+    // leave fields that are already set alone
+    #[serde(default)]
+    pub keep_existing: bool,
+    // strip surrounding whitespace from every capture
+    #[serde(default)]
+    pub trim: bool,
+}
+
+fn default_source() -> String {
+    "instruction_original".to_string()
+}
+
+pub struct Extractor {
+    rules: Vec<(Rule, Regex)>,
+}
+
+impl Extractor {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read rules {}", path.display()))?;
+        Self::from_toml_str(&raw).with_context(|| format!("invalid rules {}", path.display()))
+    }
+
+    pub fn from_toml_str(raw: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(raw)?;
+        if file.rules.is_empty() {
+            bail!("no [[rules]]");
+        }
+        let mut rules = Vec::new();
+        for rule in file.rules {
+            let re = Regex::new(&rule.pattern)
+                .with_context(|| format!("rule {}: bad pattern", rule.name))?;
+            if re.capture_names().flatten().next().is_none() {
+                bail!("rule {}: pattern has no named group (?P<field>...)", rule.name);
+            }
+            rules.push((rule, re));
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|(r, _)| r.name.as_str())
+    }
+
+    // Apply every rule to one record; returns the names of the rules that matched
+    pub fn apply(&self, obj: &mut Map<String, Value>) -> Vec<&str> {
+        let mut matched = Vec::new();
+        for (rule, re) in &self.rules {
+            let Some(text) = obj.get(&rule.source).and_then(Value::as_str).map(str::to_string) else {
+                continue;
+            };
+            let fields = if rule.multiple { capture_all(re, &text, rule.trim) } else { capture_first(re, &text, rule.trim) };
+            if fields.is_empty() {
+                continue;
+            }
+            matched.push(rule.name.as_str());
+            for (field, value) in fields {
+                if rule.keep_existing && obj.contains_key(&field) {
+                    continue;
+                }
+                obj.insert(field, value);
+            }
+        }
+        matched
+    }
+}
+
+fn capture_value(m: regex::Match, trim: bool) -> Value {
+    let s = m.as_str();
+    Value::String(if trim { s.trim() } else { s }.to_string())
+}
+
+fn capture_first(re: &Regex, text: &str, trim: bool) -> Vec<(String, Value)> {
+    let Some(caps) = re.captures(text) else { return Vec::new() };
+    re.capture_names()
+        .flatten()
+        .filter_map(|name| {
+            let m = caps.name(name)?;
+            Some((name.to_string(), capture_value(m, trim)))
+        })
+        .collect()
+}
+
+fn capture_all(re: &Regex, text: &str, trim: bool) -> Vec<(String, Value)> {
+    let all: Vec<_> = re.captures_iter(text).collect();
+    if all.is_empty() {
+        return Vec::new();
+    }
+    re.capture_names()
+        .flatten()
+        .map(|name| {
+            let values = all
+                .iter()
+                .filter_map(|caps| caps.name(name))
+                .map(|m| capture_value(m, trim))
+                .collect();
+            (name.to_string(), Value::Array(values))
+        })
+        .collect()
+}
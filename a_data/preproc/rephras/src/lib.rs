@@ -0,0 +1,19 @@
+// Shared pieces of the paraphrase generators
+
+pub mod batch;
+pub mod candidates;
+pub mod checkpoint;
+pub mod compose;
+pub mod extract;
+pub mod limiter;
+pub mod perturb;
+pub mod profile;
+pub mod provider;
+pub mod review;
+pub mod scrub;
+pub mod similarity;
+pub mod taxonomy;
+pub mod validate;
+
+// The run-wide modules shared with the judges
+pub use paraphrx_core::{cache, dryrun, usage};
@@ -0,0 +1,84 @@
+// Token-bucket request limiter shared by all generation tasks.
+//
+// The bucket refills at `rpm / 60` tokens per second and holds at most one
+// minute's worth of tokens. When a provider answers 429 (or sends
+// Retry-After) any task can call `backoff`, which pauses *every* task until
+// the deadline has passed instead of letting the others keep hammering the API.
+
+use std::time::Duration;
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+
+pub struct RateLimiter {
+    // tokens per second; `None` means unlimited
+    rate: Option<f64>,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    // `rpm == 0` disables the bucket (global backoff still applies)
+    pub fn new(rpm: u32) -> Self {
+        let rate = (rpm > 0).then(|| f64::from(rpm) / 60.0);
+        let capacity = f64::from(rpm.max(1));
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(BucketState {
+                // start with a single token so a fresh run does not burst a full minute
+                tokens: 1.0,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    // Wait until a request may be sent
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut st = self.state.lock().await;
+                let now = Instant::now();
+
+                match st.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ => {
+                        st.paused_until = None;
+                        let Some(rate) = self.rate else { return };
+
+                        let elapsed = now.duration_since(st.last_refill).as_secs_f64();
+                        st.tokens = (st.tokens + elapsed * rate).min(self.capacity);
+                        st.last_refill = now;
+
+                        if st.tokens >= 1.0 {
+                            st.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - st.tokens) / rate)
+                    }
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+
+    // Pause all tasks for at least `delay`; overlapping calls keep the later deadline
+    pub async fn backoff(&self, delay: Duration) {
+        let mut st = self.state.lock().await;
+        let until = Instant::now() + delay;
+        if st.paused_until.is_none_or(|current| current < until) {
+            st.paused_until = Some(until);
+            // resume with a single token rather than whatever piled up during the pause
+            st.tokens = 1.0;
+            st.last_refill = until;
+        }
+    }
+}
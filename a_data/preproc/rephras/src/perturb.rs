@@ -0,0 +1,355 @@
+// Deterministic, seeded rule-based perturbations for the mechanical
+// obstruction / syntax / speci_char keys.
+//
+// Gemini is unreliable at producing e.g. exact ROT13 or Base64, and cannot
+// give a controlled amount of noise. Each rule here writes into the same
+// `instruct_*` key an LLM would, and reports how much it changed so runs can
+// be compared at fixed noise intensities.
+
+use base64::Engine as _;
+use rand::{seq::index::sample, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// Top-level record key holding `{instruct_key: NoiseMeta}`; deliberately not
+// `instruct_`-prefixed so the assessment tools do not treat it as a paraphrase
+pub const META_KEY: &str = "perturbations";
+
+// Every key with a local rule, in the order they appear in the taxonomy
+pub const RULE_KEYS: &[&str] = &[
+    "instruct_typo_swap",
+    "instruct_leet_speak",
+    "instruct_all_caps",
+    "instruct_random_caps",
+    "instruct_no_punct",
+    "instruct_zero_width",
+    "instruct_zalgo",
+    "instruct_no_spaces",
+    "instruct_reversed_text",
+    "instruct_rot13",
+    "instruct_base64",
+];
+
+// Provenance stored next to the perturbed text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseMeta {
+    pub rule: String,
+    pub seed: u64,
+    // requested intensity in [0, 1]; meaning depends on the rule (see `intensity_unit`)
+    pub intensity: f64,
+    // what one edit is (letter swap, inserted mark, removed char, ...)
+    pub unit: String,
+    // number of edits actually applied
+    pub edits: usize,
+    // number of places an edit could have been applied
+    pub eligible: usize,
+}
+
+impl NoiseMeta {
+    pub fn edit_rate(&self) -> f64 {
+        if self.eligible == 0 {
+            0.0
+        } else {
+            self.edits as f64 / self.eligible as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Perturbed {
+    pub text: String,
+    pub meta: NoiseMeta,
+}
+
+pub fn has_rule(key: &str) -> bool {
+    RULE_KEYS.contains(&key)
+}
+
+// Intensity used when the caller does not ask for one
+pub fn default_intensity(key: &str) -> f64 {
+    match key {
+        "instruct_typo_swap" => 0.2,
+        "instruct_random_caps" => 0.5,
+        _ => 1.0,
+    }
+}
+
+// What `intensity` controls for each rule
+pub fn intensity_unit(key: &str) -> &'static str {
+    match key {
+        "instruct_typo_swap" => "share of words with one adjacent-letter swap",
+        "instruct_leet_speak" => "share of substitutable letters replaced",
+        "instruct_random_caps" => "share of letters with flipped case",
+        "instruct_zero_width" => "share of character gaps with a zero-width space",
+        "instruct_zalgo" => "share of letters decorated with combining marks",
+        "instruct_no_punct" => "share of punctuation marks removed",
+        "instruct_no_spaces" => "share of spaces removed",
+        _ => "ignored (whole-text transform)",
+    }
+}
+
+// Stable per-(seed, record, key) seed so a single record can be regenerated
+// on its own and still get the same noise. FNV-1a + splitmix64 finaliser:
+// unlike `DefaultHasher` this is fixed across Rust releases.
+pub fn derive_seed(seed: u64, prompt_count: u32, key: &str) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in seed
+        .to_le_bytes()
+        .iter()
+        .chain(prompt_count.to_le_bytes().iter())
+        .chain(key.as_bytes())
+    {
+        h ^= u64::from(*b);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+// Apply the rule for `key`; `None` if the key has no local rule
+pub fn apply(key: &str, text: &str, seed: u64, intensity: f64) -> Option<Perturbed> {
+    let p = intensity.clamp(0.0, 1.0);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let (out, edits, eligible, unit) = match key {
+        "instruct_typo_swap" => {
+            let (t, e, n) = typo_swap(text, p, &mut rng);
+            (t, e, n, "swap")
+        }
+        "instruct_leet_speak" => {
+            let (t, e, n) = per_char(text, p, &mut rng, |c| leet(c).is_some(), |c, _| {
+                leet(c).map(String::from).unwrap_or_default()
+            });
+            (t, e, n, "substitution")
+        }
+        "instruct_all_caps" => {
+            let (t, e, n) = per_char(text, 1.0, &mut rng, |c| c.is_lowercase(), |c, _| {
+                c.to_uppercase().collect()
+            });
+            (t, e, n, "uppercased letter")
+        }
+        "instruct_random_caps" => {
+            let (t, e, n) = per_char(text, p, &mut rng, |c| c.is_alphabetic(), |c, _| flip_case(c));
+            (t, e, n, "flipped letter")
+        }
+        "instruct_no_punct" => {
+            let (t, e, n) = per_char(text, p, &mut rng, is_punct, |_, _| String::new());
+            (t, e, n, "removed mark")
+        }
+        "instruct_no_spaces" => {
+            let (t, e, n) = per_char(text, p, &mut rng, |c| c == ' ', |_, _| String::new());
+            (t, e, n, "removed space")
+        }
+        "instruct_zero_width" => {
+            let (t, e, n) = zero_width(text, p, &mut rng);
+            (t, e, n, "inserted zero-width space")
+        }
+        "instruct_zalgo" => {
+            let (t, e, n) = per_char(text, p, &mut rng, |c| c.is_alphabetic(), zalgo_char);
+            (t, e, n, "decorated letter")
+        }
+        "instruct_reversed_text" => {
+            let n = text.chars().count();
+            (text.chars().rev().collect(), n, n, "reversed text")
+        }
+        "instruct_rot13" => {
+            let (t, e, n) = per_char(text, 1.0, &mut rng, |c| c.is_ascii_alphabetic(), |c, _| {
+                rot13(c).to_string()
+            });
+            (t, e, n, "rotated letter")
+        }
+        "instruct_base64" => {
+            let n = text.chars().count();
+            (base64::engine::general_purpose::STANDARD.encode(text), n, n, "encoded text")
+        }
+        _ => return None,
+    };
+
+    Some(Perturbed {
+        text: out,
+        meta: NoiseMeta {
+            rule: key.trim_start_matches("instruct_").to_string(),
+            seed,
+            intensity: p,
+            unit: unit.to_string(),
+            edits,
+            eligible,
+        },
+    })
+}
+
+// Fill every rule-backed key of `keys` into a record's extra map and note its
+// noise under `META_KEY`. Keys without a rule are left alone; returns how many
+// were filled. `intensity == None` uses each rule's default.
+pub fn perturb_into(
+    extra: &mut Map<String, Value>,
+    original: &str,
+    prompt_count: u32,
+    keys: &[String],
+    seed: u64,
+    intensity: Option<f64>,
+) -> usize {
+    let mut filled = 0;
+    for key in keys {
+        let p = intensity.unwrap_or_else(|| default_intensity(key));
+        let Some(res) = apply(key, original, derive_seed(seed, prompt_count, key), p) else {
+            continue;
+        };
+        extra.insert(key.clone(), Value::String(res.text));
+
+        let mut meta = serde_json::to_value(&res.meta).expect("NoiseMeta serialises");
+        meta["edit_rate"] = Value::from(res.meta.edit_rate());
+        let slot = extra
+            .entry(META_KEY)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !slot.is_object() {
+            *slot = Value::Object(Map::new());
+        }
+        slot.as_object_mut().expect("checked above").insert(key.clone(), meta);
+        filled += 1;
+    }
+    filled
+}
+
+// Pick exactly round(p * n) of the n eligible positions (at least one when
+// p > 0), so the edit count is a controlled dose rather than a coin flip per char
+fn pick(n: usize, p: f64, rng: &mut ChaCha8Rng) -> Vec<bool> {
+    let mut chosen = vec![false; n];
+    if n == 0 || p <= 0.0 {
+        return chosen;
+    }
+    let k = ((p * n as f64).round() as usize).clamp(1, n);
+    for i in sample(rng, n, k) {
+        chosen[i] = true;
+    }
+    chosen
+}
+
+// Rewrite the chosen share of eligible chars with `f`; returns (text, edits, eligible)
+fn per_char(
+    text: &str,
+    p: f64,
+    rng: &mut ChaCha8Rng,
+    eligible: impl Fn(char) -> bool,
+    f: impl Fn(char, &mut ChaCha8Rng) -> String,
+) -> (String, usize, usize) {
+    let n = text.chars().filter(|c| eligible(*c)).count();
+    let chosen = pick(n, p, rng);
+
+    let mut out = String::with_capacity(text.len());
+    let mut idx = 0;
+    let mut edits = 0;
+    for c in text.chars() {
+        if eligible(c) {
+            if chosen[idx] {
+                out.push_str(&f(c, rng));
+                edits += 1;
+            } else {
+                out.push(c);
+            }
+            idx += 1;
+        } else {
+            out.push(c);
+        }
+    }
+    (out, edits, n)
+}
+
+fn typo_swap(text: &str, p: f64, rng: &mut ChaCha8Rng) -> (String, usize, usize) {
+    // words = maximal runs of alphabetic chars with at least two distinct letters
+    let chars: Vec<char> = text.chars().collect();
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_alphabetic() {
+            let start = i;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            if chars[start..i].windows(2).any(|w| w[0] != w[1]) {
+                words.push((start, i));
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    let chosen = pick(words.len(), p, rng);
+    let mut out = chars.clone();
+    let mut edits = 0;
+    for (&(start, end), &hit) in words.iter().zip(&chosen) {
+        if !hit {
+            continue;
+        }
+        // only swap positions where the result differs from the original
+        let spots: Vec<usize> = (start..end - 1).filter(|&j| out[j] != out[j + 1]).collect();
+        let j = spots[rng.gen_range(0..spots.len())];
+        out.swap(j, j + 1);
+        edits += 1;
+    }
+    (out.into_iter().collect(), edits, words.len())
+}
+
+fn zero_width(text: &str, p: f64, rng: &mut ChaCha8Rng) -> (String, usize, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    let gaps = chars.len().saturating_sub(1);
+    let chosen = pick(gaps, p, rng);
+
+    let mut out = String::with_capacity(text.len() + gaps * 3);
+    let mut edits = 0;
+    for (i, c) in chars.iter().enumerate() {
+        out.push(*c);
+        if i < gaps && chosen[i] {
+            out.push('\u{200B}');
+            edits += 1;
+        }
+    }
+    (out, edits, gaps)
+}
+
+fn leet(c: char) -> Option<&'static str> {
+    Some(match c.to_ascii_lowercase() {
+        'a' => "4",
+        'e' => "3",
+        'i' => "1",
+        'o' => "0",
+        's' => "5",
+        't' => "7",
+        'g' => "9",
+        'b' => "8",
+        _ => return None,
+    })
+}
+
+fn flip_case(c: char) -> String {
+    if c.is_uppercase() {
+        c.to_lowercase().collect()
+    } else {
+        c.to_uppercase().collect()
+    }
+}
+
+// ASCII punctuation plus the typographic marks models like to emit
+pub fn is_punct(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c, '‘' | '’' | '“' | '”' | '–' | '—' | '…' | '¿' | '¡' | '«' | '»' | '‽')
+}
+
+pub(crate) fn rot13(c: char) -> char {
+    match c {
+        'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+        'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+        _ => c,
+    }
+}
+
+// One to three combining marks (U+0300..U+036F) stacked on the letter
+fn zalgo_char(c: char, rng: &mut ChaCha8Rng) -> String {
+    let mut s = String::from(c);
+    for _ in 0..rng.gen_range(1..=3) {
+        s.push(char::from_u32(rng.gen_range(0x0300..=0x036F)).expect("combining mark range"));
+    }
+    s
+}
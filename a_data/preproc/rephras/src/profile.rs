@@ -0,0 +1,157 @@
+// Dataset profiles for the paraphrase generator.
+//
+// Alpaca, GSM8K and MMLU records only differ in which field holds the text to
+// paraphrase, which fields must survive untouched, and a few extra rules for
+// the prompt. Everything not named as the source field is copied through.
+
+use crate::extract::Extractor;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Dataset {
+    Alpaca,
+    Gsm8k,
+    Mmlu,
+    // source / pass-through fields and prompt rules given on the command line
+    Custom,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    // candidate fields holding the text to paraphrase, first present one wins;
+    // it is written back as `instruction_original`
+    pub source_fields: Vec<String>,
+    // fields that must be present and are copied through unchanged
+    pub passthrough: Vec<String>,
+    // extra rules appended to the generation prompt
+    pub prompt_rules: Vec<String>,
+    // model used when `--model` is not given
+    pub default_model: String,
+    // run the `cargo add_scenarios` rules (a_data/extract_rules/scenarios.toml)
+    // on the source, so MMLU moral_scenarios records get their `scenarios`
+    // field without a separate pass
+    pub extract_scenarios: bool,
+}
+
+// Every profile's source fields also accept the already renamed field, so
+// re-running on generator output (or a journal) works
+const RENAMED: &str = "instruction_original";
+
+// Built in, so the generator runs from any directory
+const SCENARIO_RULES: &str = include_str!("../../../extract_rules/scenarios.toml");
+
+fn scenario_rules() -> &'static Extractor {
+    static RULES: OnceLock<Extractor> = OnceLock::new();
+    RULES.get_or_init(|| Extractor::from_toml_str(SCENARIO_RULES).expect("invalid a_data/extract_rules/scenarios.toml"))
+}
+
+impl Profile {
+    pub fn builtin(dataset: Dataset) -> Self {
+        let s = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        match dataset {
+            Dataset::Alpaca => Self {
+                name: "alpaca".into(),
+                source_fields: s(&[RENAMED, "instruction"]),
+                passthrough: s(&["input"]),
+                prompt_rules: Vec::new(),
+                default_model: "gemini-2.5-flash-preview-05-20".into(),
+                extract_scenarios: false,
+            },
+            Dataset::Gsm8k => Self {
+                name: "gsm8k".into(),
+                source_fields: s(&[RENAMED, "question", "prompt"]),
+                passthrough: s(&["answer"]),
+                prompt_rules: s(&[
+                    "Keep every number, quantity and unit exactly as in the original",
+                    "Do not solve the problem or hint at the answer",
+                ]),
+                default_model: "gemini-2.5-flash-preview-04-17".into(),
+                extract_scenarios: false,
+            },
+            Dataset::Mmlu => Self {
+                name: "mmlu".into(),
+                source_fields: s(&[RENAMED, "question", "prompt"]),
+                passthrough: s(&["choices", "answer"]),
+                prompt_rules: s(&[
+                    "The answer options are shown separately: do not add, drop, reorder or reword them",
+                    "Keep statements labelled \"Scenario 1\" / \"Scenario 2\" verbatim",
+                ]),
+                default_model: "gemini-2.5-flash-preview-04-17".into(),
+                extract_scenarios: true,
+            },
+            Dataset::Custom => Self {
+                name: "custom".into(),
+                source_fields: vec![RENAMED.into()],
+                passthrough: Vec::new(),
+                prompt_rules: Vec::new(),
+                default_model: "gemini-2.5-flash-preview-05-20".into(),
+                extract_scenarios: false,
+            },
+        }
+    }
+
+    // Built-in profile with command-line overrides; `custom` needs `--field`
+    pub fn resolve(
+        dataset: Dataset,
+        fields: &[String],
+        passthrough: &[String],
+        rules: &[String],
+    ) -> Result<Self> {
+        let mut profile = Self::builtin(dataset);
+        if dataset == Dataset::Custom && fields.is_empty() {
+            bail!("--dataset custom needs --field <name of the text field>");
+        }
+        if !fields.is_empty() {
+            profile.source_fields = std::iter::once(RENAMED.to_string())
+                .chain(fields.iter().cloned())
+                .collect();
+        }
+        profile.passthrough.extend(passthrough.iter().cloned());
+        profile.prompt_rules.extend(rules.iter().cloned());
+        Ok(profile)
+    }
+
+    // Split a raw record into (text to paraphrase, every other field)
+    pub fn take_source(&self, mut obj: Map<String, Value>) -> Result<(String, Map<String, Value>)> {
+        let Some(field) = self.source_fields.iter().find(|f| obj.contains_key(*f)) else {
+            bail!(
+                "none of the {} source fields ({}) present",
+                self.name,
+                self.source_fields.join(", ")
+            );
+        };
+        let text = match obj.remove(field) {
+            Some(Value::String(s)) => s,
+            _ => bail!("field {field} is not a string"),
+        };
+        if let Some(missing) = self.passthrough.iter().find(|f| !obj.contains_key(*f)) {
+            bail!("{} field {missing} missing", self.name);
+        }
+
+        if self.extract_scenarios {
+            // the rules read `instruction_original`; fields already in the
+            // record win
+            let mut fields = Map::new();
+            fields.insert(RENAMED.into(), Value::String(text.clone()));
+            scenario_rules().apply(&mut fields);
+            fields.remove(RENAMED);
+            for (k, v) in fields {
+                obj.entry(k).or_insert(v);
+            }
+        }
+        Ok((text, obj))
+    }
+
+    // "**Dataset rules:**" block for the prompt, empty when there are none
+    pub fn rules_block(&self) -> String {
+        if self.prompt_rules.is_empty() {
+            return String::new();
+        }
+        let rules: Vec<String> = self.prompt_rules.iter().map(|r| format!("* {r}.")).collect();
+        format!("**Dataset rules:**\n{}\n\n", rules.join("\n"))
+    }
+}
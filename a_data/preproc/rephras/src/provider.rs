@@ -0,0 +1,477 @@
+// LLM backends for paraphrase generation.
+//
+// Every backend takes the rendered prompt plus the JSON schema built by
+// `schema_for` and hands back the parsed JSON object, so the record loop does
+// not care whether the variants come from Gemini, a self-hosted open model or
+// the offline mock. Token counts of each response go to the usage ledger;
+// with a response cache, identical requests are answered from disk.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use clap::ValueEnum;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, RETRY_AFTER},
+    StatusCode,
+};
+use crate::cache::Cache;
+use crate::usage::{self, Usage};
+use serde_json::{json, Map, Value};
+use std::{env, fmt, future::Future, sync::Arc, time::Duration};
+
+pub const GEMINI_ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+// Default base URL for OpenAI-compatible servers (vLLM serves on :8000/v1)
+pub const OPENAI_DEFAULT_BASE: &str = "http://localhost:8000/v1";
+
+// Which backend to talk to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProviderKind {
+    Gemini,
+    // Any server speaking the OpenAI chat/completions protocol
+    // (OpenAI, vLLM, llama.cpp server, Ollama)
+    Openai,
+    // Offline stand-in: fills every schema key from the prompt, no network
+    Mock,
+}
+
+// How the schema is handed to an OpenAI-compatible server
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StructuredMode {
+    // `response_format: {type: json_schema}` - OpenAI, vLLM, llama.cpp, Ollama
+    ResponseFormat,
+    // `guided_json` extra body field - vLLM guided decoding
+    GuidedJson,
+    // top-level `json_schema` field - llama.cpp server grammar from schema
+    JsonSchema,
+    // `response_format: {type: json_object}` - schema only described in the prompt
+    JsonObject,
+}
+
+// Everything needed to construct a provider from the CLI
+#[derive(Clone, Debug)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub structured: StructuredMode,
+    pub timeout: Duration,
+    pub cache: Option<Arc<Cache>>,
+}
+
+#[async_trait]
+pub trait Provider: Send + Sync {
+    // Short label used in logs
+    fn name(&self) -> &str;
+
+    // Send one structured-output request and return the parsed JSON object
+    async fn generate_json(&self, prompt: &str, schema: &Value) -> Result<Map<String, Value>>;
+
+    // Drop the cached response to this request, if any, after the caller
+    // rejected every value in it
+    fn forget(&self, _prompt: &str, _schema: &Value) {}
+
+    // Whether this request would be answered from the response cache; such a
+    // replay sends nothing, so it needs no rate-limit token
+    fn is_cached(&self, _prompt: &str, _schema: &Value) -> bool {
+        false
+    }
+}
+
+pub fn build_provider(cfg: &ProviderConfig) -> Result<Box<dyn Provider>> {
+    let provider: Box<dyn Provider> = match cfg.kind {
+        ProviderKind::Gemini => {
+            let key = match &cfg.api_key {
+                Some(k) => k.clone(),
+                None => env::var("GOOGLE_API_KEY").context("GOOGLE_API_KEY not set")?,
+            };
+            Box::new(GeminiProvider {
+                client: build_client(cfg.timeout)?,
+                endpoint: cfg
+                    .base_url
+                    .clone()
+                    .unwrap_or_else(|| GEMINI_ENDPOINT.to_string()),
+                key,
+                model: cfg.model.clone(),
+                cache: cfg.cache.clone(),
+            })
+        }
+        ProviderKind::Openai => Box::new(OpenAiProvider {
+            client: build_client(cfg.timeout)?,
+            base_url: cfg
+                .base_url
+                .clone()
+                .unwrap_or_else(|| OPENAI_DEFAULT_BASE.to_string()),
+            // local servers usually run without a key
+            key: cfg.api_key.clone().or_else(|| env::var("OPENAI_API_KEY").ok()),
+            model: cfg.model.clone(),
+            structured: cfg.structured,
+            cache: cfg.cache.clone(),
+        }),
+        ProviderKind::Mock => Box::new(MockProvider),
+    };
+    Ok(provider)
+}
+
+pub fn build_client(timeout: Duration) -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(timeout)
+        .build()?;
+
+    Ok(client)
+}
+
+// Returned (inside `anyhow::Error`) when the server throttles us, so callers
+// can downcast it and pause every task instead of just retrying this one
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.retry_after {
+            Some(d) => write!(f, "rate limited (retry after {:.1}s) — {}", d.as_secs_f64(), self.message),
+            None => write!(f, "rate limited — {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+// Turn a non-2xx response into an error, classifying throttling separately
+async fn error_for_status(resp: reqwest::Response) -> anyhow::Error {
+    let status = resp.status();
+    let header_delay = resp
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(retry_after_delay);
+    let msg = resp.text().await.unwrap_or_default();
+
+    let throttled = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::SERVICE_UNAVAILABLE && header_delay.is_some());
+    if throttled {
+        return RateLimited {
+            retry_after: header_delay.or_else(|| gemini_retry_delay(&msg)),
+            message: format!("{status} — {msg}"),
+        }
+        .into();
+    }
+    anyhow!("{} — {}", status, msg)
+}
+
+// `Retry-After` is either delay-seconds or an HTTP-date
+// (`Wed, 21 Oct 2015 07:28:00 GMT`); a date in the past means "now"
+fn retry_after_delay(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+// Gemini puts the hint in the body: `"retryDelay": "24s"` inside a RetryInfo detail
+fn gemini_retry_delay(body: &str) -> Option<Duration> {
+    let v: Value = serde_json::from_str(body).ok()?;
+    v["error"]["details"]
+        .as_array()?
+        .iter()
+        .filter_map(|d| d["retryDelay"].as_str())
+        .find_map(|d| d.trim_end_matches('s').parse::<f64>().ok())
+        .map(Duration::from_secs_f64)
+}
+
+// Cached response to `key`, else the result of `send`; the flag says whether
+// the response is fresh (and should be stored once it parsed)
+async fn cached_or_send(
+    cache: Option<&Cache>,
+    key: &str,
+    send: impl Future<Output = Result<Value>>,
+) -> Result<(Value, bool)> {
+    match cache.and_then(|c| c.get(key)) {
+        Some(hit) => Ok((hit, false)),
+        None => Ok((send.await?, true)),
+    }
+}
+
+// Parse the JSON object a model returned, tolerating code fences and chatter
+// around the object (open models without grammar support like to add both)
+pub fn parse_json_object(text: &str, source: &str) -> Result<Map<String, Value>> {
+    let cleaned = text
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    if let Ok(map) = serde_json::from_str(cleaned) {
+        return Ok(map);
+    }
+    if let (Some(start), Some(end)) = (cleaned.find('{'), cleaned.rfind('}')) {
+        if let Ok(map) = serde_json::from_str(&cleaned[start..=end]) {
+            return Ok(map);
+        }
+    }
+
+    // Attach the offending string so the log shows what came back
+    serde_json::from_str(cleaned).map_err(|e| {
+        anyhow!("failed to parse JSON returned by {source}: {e}\njson_text:\n{text}")
+    })
+}
+
+// Gemini generateContent with `responseSchema`
+pub struct GeminiProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    key: String,
+    model: String,
+    cache: Option<Arc<Cache>>,
+}
+
+impl GeminiProvider {
+    // URL without the API key, and request body
+    fn request(&self, prompt: &str, schema: &Value) -> (String, Value) {
+        let url = format!("{}/models/{}:generateContent", self.endpoint, self.model);
+        let body = json!({
+            "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+            "generationConfig": {
+                "responseMimeType": "application/json",
+                "responseSchema":  schema
+            }
+        });
+        (url, body)
+    }
+
+    async fn send(&self, url: &str, body: &Value) -> Result<Value> {
+        let resp = self
+            .client
+            .post(format!("{url}?key={}", self.key))
+            .json(body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(error_for_status(resp).await);
+        }
+
+        let resp_json: Value = resp.json().await?;
+        if let Some(u) = Usage::from_gemini(&resp_json) {
+            usage::record(&self.model, u);
+        }
+        Ok(resp_json)
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    async fn generate_json(&self, prompt: &str, schema: &Value) -> Result<Map<String, Value>> {
+        let (url, body) = self.request(prompt, schema);
+        let cache_key = Cache::key(&url, &self.model, &body);
+        let (resp_json, fresh) =
+            cached_or_send(self.cache.as_deref(), &cache_key, self.send(&url, &body)).await?;
+
+        // Gracefully report any layout surprises with the full payload
+        let json_text = resp_json["candidates"][0]["content"]["parts"][0]["text"]
+            .as_str()
+            .ok_or_else(|| {
+                anyhow!(
+                    "unexpected response structure; full JSON from Gemini:\n{}",
+                    serde_json::to_string_pretty(&resp_json)
+                        .unwrap_or_else(|_| "<unable to serialise>".to_string())
+                )
+            })?;
+
+        let map = parse_json_object(json_text, "Gemini")?;
+        if let (true, Some(cache)) = (fresh, &self.cache) {
+            cache.put(&cache_key, &resp_json);
+        }
+        Ok(map)
+    }
+
+    fn forget(&self, prompt: &str, schema: &Value) {
+        if let Some(cache) = &self.cache {
+            let (url, body) = self.request(prompt, schema);
+            cache.forget(&Cache::key(&url, &self.model, &body));
+        }
+    }
+
+    fn is_cached(&self, prompt: &str, schema: &Value) -> bool {
+        self.cache.as_ref().is_some_and(|cache| {
+            let (url, body) = self.request(prompt, schema);
+            cache.contains(&Cache::key(&url, &self.model, &body))
+        })
+    }
+}
+
+// OpenAI-compatible /chat/completions
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    base_url: String,
+    key: Option<String>,
+    model: String,
+    structured: StructuredMode,
+    cache: Option<Arc<Cache>>,
+}
+
+impl OpenAiProvider {
+    fn request(&self, prompt: &str, schema: &Value) -> (String, Value) {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": prompt }],
+        });
+        let obj = body.as_object_mut().expect("body is an object");
+        match self.structured {
+            StructuredMode::ResponseFormat => {
+                obj.insert(
+                    "response_format".into(),
+                    json!({
+                        "type": "json_schema",
+                        "json_schema": { "name": "paraphrases", "schema": strict_schema(schema), "strict": true }
+                    }),
+                );
+            }
+            StructuredMode::GuidedJson => {
+                obj.insert("guided_json".into(), schema.clone());
+            }
+            StructuredMode::JsonSchema => {
+                obj.insert("json_schema".into(), schema.clone());
+            }
+            StructuredMode::JsonObject => {
+                obj.insert("response_format".into(), json!({ "type": "json_object" }));
+            }
+        }
+        (url, body)
+    }
+
+    async fn send(&self, url: &str, body: &Value) -> Result<Value> {
+        let mut req = self.client.post(url).json(body);
+        if let Some(key) = &self.key {
+            req = req.bearer_auth(key);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(error_for_status(resp).await);
+        }
+
+        let resp_json: Value = resp.json().await?;
+        if let Some(u) = Usage::from_openai(&resp_json) {
+            usage::record(&self.model, u);
+        }
+        Ok(resp_json)
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn generate_json(&self, prompt: &str, schema: &Value) -> Result<Map<String, Value>> {
+        let (url, body) = self.request(prompt, schema);
+        let cache_key = Cache::key(&url, &self.model, &body);
+        let (resp_json, fresh) =
+            cached_or_send(self.cache.as_deref(), &cache_key, self.send(&url, &body)).await?;
+
+        let json_text = resp_json["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| {
+                anyhow!(
+                    "unexpected response structure; full JSON from {}:\n{}",
+                    self.base_url,
+                    serde_json::to_string_pretty(&resp_json)
+                        .unwrap_or_else(|_| "<unable to serialise>".to_string())
+                )
+            })?;
+
+        let map = parse_json_object(json_text, &self.base_url)?;
+        if let (true, Some(cache)) = (fresh, &self.cache) {
+            cache.put(&cache_key, &resp_json);
+        }
+        Ok(map)
+    }
+
+    fn forget(&self, prompt: &str, schema: &Value) {
+        if let Some(cache) = &self.cache {
+            let (url, body) = self.request(prompt, schema);
+            cache.forget(&Cache::key(&url, &self.model, &body));
+        }
+    }
+
+    fn is_cached(&self, prompt: &str, schema: &Value) -> bool {
+        self.cache.as_ref().is_some_and(|cache| {
+            let (url, body) = self.request(prompt, schema);
+            cache.contains(&Cache::key(&url, &self.model, &body))
+        })
+    }
+}
+
+// OpenAI's strict mode rejects any object schema that allows extra keys;
+// the schemas stay open for Gemini, which does not know the keyword
+fn strict_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(obj) => {
+            let mut out: Map<String, Value> = obj.iter().map(|(k, v)| (k.clone(), strict_schema(v))).collect();
+            if obj.get("type").and_then(Value::as_str) == Some("object") {
+                out.insert("additionalProperties".into(), Value::Bool(false));
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(strict_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+// Deterministic offline backend: every schema key gets the original
+// instruction tagged with the key name, so the whole pipeline can run
+// without credentials or network
+pub struct MockProvider;
+
+// Marker after which the generator prompts place the text to rewrite
+const ORIGINAL_MARKER: &str = "Original Instruction:\n";
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn generate_json(&self, prompt: &str, schema: &Value) -> Result<Map<String, Value>> {
+        let original = prompt
+            .rfind(ORIGINAL_MARKER)
+            .map(|i| &prompt[i + ORIGINAL_MARKER.len()..])
+            .unwrap_or(prompt)
+            .trim();
+
+        let props = schema["properties"]
+            .as_object()
+            .ok_or_else(|| anyhow!("mock provider needs an object schema with properties"))?;
+
+        // array properties (candidates, judge scores) get `minItems` entries
+        Ok(props
+            .iter()
+            .map(|(k, p)| {
+                let v = match p["items"]["type"].as_str() {
+                    Some("integer") => json!(vec![5; p["minItems"].as_u64().unwrap_or(1) as usize]),
+                    Some(_) => (1..=p["minItems"].as_u64().unwrap_or(1))
+                        .map(|i| Value::String(format!("[{k} #{i}] {original}")))
+                        .collect(),
+                    None => Value::String(format!("[{k}] {original}")),
+                };
+                (k.clone(), v)
+            })
+            .collect())
+    }
+}
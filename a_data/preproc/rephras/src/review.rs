@@ -0,0 +1,99 @@
+// Human review sheets for generated paraphrases.
+//
+// `export_review` samples (prompt_count, key) pairs into a CSV/TSV sheet with
+// empty `verdict`, `corrected` and `notes` columns; annotators fill in one
+// copy each and `import_review` applies the verdicts to the paraphrase JSON.
+// Rows are matched by `prompt_count` + key, the same join `merge_instructs`
+// uses. A rejected paraphrase is moved from `<key>` to `rejected__<key>`, so
+// later steps that pick up `instruct_*` fields no longer see it.
+
+use crate::compose::STEP_SEP;
+use crate::taxonomy::Taxonomy;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, hash::Hash, path::Path};
+
+pub const REJECTED_PREFIX: &str = "rejected__";
+
+// One line of a review sheet; the last three columns are the annotator's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Row {
+    pub prompt_count: u32,
+    pub set: String,
+    pub key: String,
+    pub original: String,
+    pub paraphrase: String,
+    // validator result at export time: `ok` or the reason it failed
+    pub auto_check: String,
+    #[serde(default)]
+    pub verdict: String,
+    #[serde(default)]
+    pub corrected: String,
+    #[serde(default)]
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Ok,
+    Fix,
+    Reject,
+}
+
+impl Verdict {
+    // Annotator spelling -> verdict; an empty cell is "not reviewed"
+    pub fn parse(cell: &str) -> Result<Option<Self>> {
+        Ok(match cell.trim().to_lowercase().as_str() {
+            "" => None,
+            "ok" | "accept" | "yes" | "y" => Some(Self::Ok),
+            "fix" | "correct" | "corrected" => Some(Self::Fix),
+            "reject" | "no" | "n" => Some(Self::Reject),
+            other => bail!("unknown verdict {other:?} (use ok, fix or reject)"),
+        })
+    }
+}
+
+// `.csv` is comma separated, anything else tab separated
+pub fn delimiter(path: &Path) -> u8 {
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+        b','
+    } else {
+        b'\t'
+    }
+}
+
+// Stratum of a key: its first set in the taxonomy, else what kind of derived key it is
+pub fn set_of(taxonomy: &Taxonomy, key: &str) -> String {
+    if let Some((name, _)) = taxonomy.sets.iter().find(|(_, s)| s.keys.iter().any(|k| k == key)) {
+        name.clone()
+    } else if key.ends_with("__backtranslated") {
+        "backtranslated".to_string()
+    } else if key.contains(STEP_SEP) {
+        "composed".to_string()
+    } else {
+        "unlisted".to_string()
+    }
+}
+
+// Cohen's kappa of paired labels; `None` without pairs or when chance
+// agreement is already perfect
+pub fn cohen_kappa<T: Eq + Hash + Copy>(pairs: &[(T, T)]) -> Option<f64> {
+    if pairs.is_empty() {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let observed = pairs.iter().filter(|(a, b)| a == b).count() as f64 / n;
+    let mut left: HashMap<T, f64> = HashMap::new();
+    let mut right: HashMap<T, f64> = HashMap::new();
+    for (a, b) in pairs {
+        *left.entry(*a).or_default() += 1.0;
+        *right.entry(*b).or_default() += 1.0;
+    }
+    let expected: f64 = left
+        .iter()
+        .map(|(label, l)| l * right.get(label).copied().unwrap_or(0.0))
+        .sum::<f64>()
+        / (n * n);
+    (expected < 1.0).then(|| (observed - expected) / (1.0 - expected))
+}
@@ -0,0 +1,40 @@
+// `--scrub` for the generators: the `Scrubber` and its map file come from
+// paraphrx_core; the provider wrapper below is what puts it in front of every
+// model call.
+
+use crate::provider::Provider;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+pub use paraphrx_core::scrub::{map_path_for, Scrubber, DEFAULT_SCRUB_PATTERNS};
+
+// A provider that only ever sees scrubbed prompts; placeholders the model
+// copies into its answer are put back before the caller sees it
+pub struct ScrubbingProvider {
+    pub inner: Arc<dyn Provider>,
+    pub scrubber: Arc<Scrubber>,
+    pub set: String,
+}
+
+#[async_trait]
+impl Provider for ScrubbingProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn generate_json(&self, prompt: &str, schema: &Value) -> Result<Map<String, Value>> {
+        let prompt = self.scrubber.scrub(&self.set, prompt);
+        let map = self.inner.generate_json(&prompt, schema).await?;
+        Ok(map.into_iter().map(|(k, v)| (k, self.scrubber.restore_value(v))).collect())
+    }
+
+    fn forget(&self, prompt: &str, schema: &Value) {
+        self.inner.forget(&self.scrubber.scrub(&self.set, prompt), schema);
+    }
+
+    fn is_cached(&self, prompt: &str, schema: &Value) -> bool {
+        self.inner.is_cached(&self.scrubber.scrub(&self.set, prompt), schema)
+    }
+}
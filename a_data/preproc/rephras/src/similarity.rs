@@ -0,0 +1,52 @@
+// Surface similarity between two texts.
+//
+// chrF (Popović 2015): character n-gram F-score for n = 1..=6 averaged,
+// whitespace removed, β = 2 so recall of the reference counts double. Same
+// defaults as sacrebleu's `chrF`, scaled to [0, 1]. Used to score
+// back-translations against `instruction_original`.
+
+use std::collections::HashMap;
+
+const MAX_ORDER: usize = 6;
+const BETA: f64 = 2.0;
+
+pub fn chrf(reference: &str, hypothesis: &str) -> f64 {
+    let r: Vec<char> = reference.chars().filter(|c| !c.is_whitespace()).collect();
+    let h: Vec<char> = hypothesis.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut precision = 0.0;
+    let mut recall = 0.0;
+    let mut orders = 0;
+    for n in 1..=MAX_ORDER {
+        let (rn, hn) = (ngrams(&r, n), ngrams(&h, n));
+        let (r_total, h_total) = (rn.values().sum::<usize>(), hn.values().sum::<usize>());
+        if r_total == 0 || h_total == 0 {
+            continue;
+        }
+        let matched: usize = hn
+            .iter()
+            .map(|(g, c)| (*c).min(rn.get(g).copied().unwrap_or(0)))
+            .sum();
+        precision += matched as f64 / h_total as f64;
+        recall += matched as f64 / r_total as f64;
+        orders += 1;
+    }
+    if orders == 0 {
+        return if r.is_empty() && h.is_empty() { 1.0 } else { 0.0 };
+    }
+
+    let (p, r) = (precision / orders as f64, recall / orders as f64);
+    if p + r == 0.0 {
+        return 0.0;
+    }
+    let b2 = BETA * BETA;
+    (1.0 + b2) * p * r / (b2 * p + r)
+}
+
+fn ngrams(chars: &[char], n: usize) -> HashMap<&[char], usize> {
+    let mut out = HashMap::new();
+    for w in chars.windows(n) {
+        *out.entry(w).or_insert(0) += 1;
+    }
+    out
+}
@@ -0,0 +1,272 @@
+// Paraphrase taxonomy loaded from `a_data/paraphrase_taxonomy.toml`.
+//
+// Replaces the `VERSION_SETS` phf maps that used to be copy-pasted into every
+// generator: sets, their ordered keys, and per-key description + tags all
+// live in one data file that is read at runtime. Graded scales
+// (`[scales.<dim>]`) are expanded into an ordinary set `<dim>` with keys
+// `instruct_<dim>_L1..LN` on load, so every consumer sees them as sets.
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::{collections::HashSet, fmt::Write as _, fs, path::Path};
+
+// The scale key format is shared with summarise_scores
+pub use paraphrx_core::scale::{parse_scale_key, scale_key};
+
+// Default location, relative to the repository root (where the cargo aliases run)
+pub const DEFAULT_TAXONOMY: &str = "a_data/paraphrase_taxonomy.toml";
+
+// Markers around the generated section of categories.md
+pub const CATEGORIES_BEGIN: &str = "<!-- taxonomy:begin (generated by `cargo taxonomy export`, do not edit) -->";
+pub const CATEGORIES_END: &str = "<!-- taxonomy:end -->";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Taxonomy {
+    pub version: u32,
+    pub sets: IndexMap<String, KeySet>,
+    #[serde(default)]
+    pub keys: IndexMap<String, KeyInfo>,
+    #[serde(default)]
+    pub scales: IndexMap<String, Scale>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySet {
+    pub title: String,
+    // old names still accepted by `--version-set` (e.g. `spec_char`)
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyInfo {
+    // wording used in the generation prompt for this key
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// One ordinal dimension (politeness, verbosity, ...) with `levels` steps from
+// `low` (L1) to `high` (LN)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scale {
+    pub title: String,
+    pub levels: u32,
+    pub low: String,
+    pub high: String,
+    // wording of every level, L1 first; replaces the interpolated wording
+    #[serde(default)]
+    pub anchors: Vec<String>,
+}
+
+impl Scale {
+    // Prompt wording of one level
+    pub fn describe(&self, dim: &str, level: u32) -> String {
+        let n = self.levels;
+        let head = format!("level {level} of {n} on the {dim} scale");
+        match self.anchors.get(level as usize - 1) {
+            Some(anchor) => format!("{head}: {anchor}"),
+            None if level == 1 => format!("{head}, the lowest: {}", self.low),
+            None if level == n => format!("{head}, the highest: {}", self.high),
+            None => format!(
+                "{head}, between \"{}\" (L1) and \"{}\" (L{n}), clearly above L{} and below L{}",
+                self.low,
+                self.high,
+                level - 1,
+                level + 1
+            ),
+        }
+    }
+}
+
+impl Taxonomy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read taxonomy {}", path.display()))?;
+        Self::from_toml_str(&raw).with_context(|| format!("invalid taxonomy {}", path.display()))
+    }
+
+    pub fn from_toml_str(raw: &str) -> Result<Self> {
+        let mut tax: Taxonomy = toml::from_str(raw)?;
+        tax.expand_scales()?;
+        tax.validate()?;
+        Ok(tax)
+    }
+
+    // Every scale becomes the set `<dim>` of its level keys; keys without an
+    // own `[keys.*]` entry get the level wording and the tags `scale`, `<dim>`
+    fn expand_scales(&mut self) -> Result<()> {
+        for (dim, scale) in &self.scales {
+            if scale.levels < 2 {
+                bail!("scale {dim}: needs at least 2 levels, has {}", scale.levels);
+            }
+            if !scale.anchors.is_empty() && scale.anchors.len() != scale.levels as usize {
+                bail!(
+                    "scale {dim}: {} anchors for {} levels",
+                    scale.anchors.len(),
+                    scale.levels
+                );
+            }
+            if self.sets.contains_key(dim) {
+                bail!("scale {dim} clashes with the set of the same name");
+            }
+            let keys: Vec<String> = (1..=scale.levels).map(|l| scale_key(dim, l)).collect();
+            for (key, level) in keys.iter().zip(1..) {
+                let info = self.keys.entry(key.clone()).or_default();
+                if info.description.is_none() {
+                    info.description = Some(scale.describe(dim, level));
+                }
+                if info.tags.is_empty() {
+                    info.tags = vec!["scale".to_string(), dim.clone()];
+                }
+            }
+            self.sets.insert(
+                dim.clone(),
+                KeySet { title: scale.title.clone(), aliases: Vec::new(), keys },
+            );
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.version != 1 {
+            bail!("unsupported taxonomy version {}", self.version);
+        }
+
+        let mut names: HashSet<&str> = self.sets.keys().map(String::as_str).collect();
+        for (name, set) in &self.sets {
+            for alias in &set.aliases {
+                if !names.insert(alias) {
+                    bail!("set alias {alias} (of {name}) clashes with another set or alias");
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for key in &set.keys {
+                if key.is_empty() || key.chars().any(char::is_whitespace) {
+                    bail!("set {name}: key {key:?} must be non-empty and contain no spaces");
+                }
+                if !seen.insert(key) {
+                    bail!("set {name}: key {key} listed twice");
+                }
+            }
+        }
+
+        for key in self.keys.keys() {
+            if key.chars().any(char::is_whitespace) {
+                bail!("key {key:?} contains whitespace");
+            }
+        }
+        Ok(())
+    }
+
+    // Look up a set by name or alias; returns the canonical name too
+    pub fn resolve_set(&self, name: &str) -> Result<(&str, &KeySet)> {
+        if let Some((canonical, set)) = self.sets.get_key_value(name) {
+            return Ok((canonical.as_str(), set));
+        }
+        self.sets
+            .iter()
+            .find(|(_, set)| set.aliases.iter().any(|a| a == name))
+            .map(|(canonical, set)| (canonical.as_str(), set))
+            .with_context(|| {
+                format!(
+                    "unknown version set {name} (available: {})",
+                    self.sets.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+
+    // The scale a level key belongs to: (dimension, scale, level)
+    pub fn scale_of(&self, key: &str) -> Option<(&str, &Scale, u32)> {
+        let (dim, level) = parse_scale_key(key)?;
+        let (dim, scale) = self.scales.get_key_value(dim)?;
+        (level <= scale.levels).then_some((dim.as_str(), scale, level))
+    }
+
+    pub fn description(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).and_then(|k| k.description.as_deref())
+    }
+
+    // Keys that put the instruction into another language (tag `language`,
+    // but not an English dialect or register) – the ones worth back-translating
+    pub fn is_translation(&self, key: &str) -> bool {
+        self.keys.get(key).is_some_and(|k| {
+            k.tags.iter().any(|t| t == "language")
+                && !k.tags.iter().any(|t| t == "dialect" || t == "register")
+        })
+    }
+
+    // Every key mentioned anywhere (sets first, in file order, then metadata-only keys)
+    pub fn all_keys(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.sets
+            .values()
+            .flat_map(|s| s.keys.iter())
+            .chain(self.keys.keys())
+            .map(String::as_str)
+            .filter(|k| seen.insert(*k))
+            .collect()
+    }
+
+    // Contents of a_data/paraphrases_tagged.json: sorted keys, one line per key
+    pub fn render_tags_json(&self) -> String {
+        let mut tagged: Vec<(&String, &KeyInfo)> =
+            self.keys.iter().filter(|(_, info)| !info.tags.is_empty()).collect();
+        tagged.sort_by(|a, b| a.0.cmp(b.0));
+
+        let lines: Vec<String> = tagged
+            .iter()
+            .map(|(key, info)| {
+                let tags: Vec<String> = info
+                    .tags
+                    .iter()
+                    .map(|t| serde_json::to_string(t).expect("string serialises"))
+                    .collect();
+                format!("  {}: [{}]", serde_json::to_string(key).expect("string serialises"), tags.join(", "))
+            })
+            .collect();
+        format!("{{\n{}\n}}\n", lines.join(",\n"))
+    }
+
+    // Generated section of categories.md (markers included)
+    pub fn render_categories_block(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{CATEGORIES_BEGIN}");
+        let _ = writeln!(out, "## Generator key sets\n");
+        for (name, set) in &self.sets {
+            let _ = write!(out, "### {} (`{name}`, {} keys)", set.title, set.keys.len());
+            if !set.aliases.is_empty() {
+                let _ = write!(out, " – also accepted as `{}`", set.aliases.join("`, `"));
+            }
+            let _ = writeln!(out, "\n");
+            for key in &set.keys {
+                match self.description(key) {
+                    Some(d) => { let _ = writeln!(out, "- `{key}` – {d}"); }
+                    None => { let _ = writeln!(out, "- `{key}`"); }
+                }
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out, "{CATEGORIES_END}");
+        out
+    }
+}
+
+// Replace (or append) the generated block inside categories.md
+pub fn splice_categories(existing: &str, block: &str) -> String {
+    match (existing.find(CATEGORIES_BEGIN), existing.find(CATEGORIES_END)) {
+        (Some(start), Some(end)) if end > start => {
+            let tail = &existing[end + CATEGORIES_END.len()..];
+            format!("{}{}{}", &existing[..start], block, tail.trim_start_matches('\n'))
+        }
+        _ => format!("{}\n\n{}", existing.trim_end(), block),
+    }
+}
@@ -0,0 +1,307 @@
+// Post-generation checks that a variant actually obeys its key.
+//
+// Only keys with a checkable surface form are registered (casing, encodings,
+// character classes, length); stylistic keys are left to the assessment
+// step. Every key of a set is additionally required to be a non-empty string.
+
+use crate::perturb::{is_punct, rot13};
+use base64::Engine as _;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+// What the generator does with a variant that fails its check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ValidateMode {
+    // re-request the record (uses up an attempt), flag it if attempts run out
+    Retry,
+    // keep the first answer and only flag it in the report
+    Report,
+    Off,
+}
+
+// `Err` carries a short human-readable reason
+pub type Check = fn(original: &str, variant: &str) -> Result<(), String>;
+
+// Registry: paraphrase key -> check
+const VALIDATORS: &[(&str, Check)] = &[
+    ("instruct_all_caps", all_caps),
+    ("instruct_no_caps", no_caps),
+    ("instruct_random_caps", random_caps),
+    ("instruct_base64", base64),
+    ("instruct_rot13", rot13_round_trip),
+    ("instruct_reversed_text", reversed_text),
+    ("instruct_emoji_only", emoji_only),
+    ("instruct_fewest_words", fewest_words),
+    ("instruct_few_words", few_words),
+    ("instruct_no_spaces", no_spaces),
+    ("instruct_no_punct", no_punct),
+    ("instruct_zero_width", zero_width),
+    ("instruct_zalgo", zalgo),
+    ("instruct_leet_speak", leet_speak),
+    ("instruct_morse_code", morse_code),
+    ("instruct_typo_swap", changed),
+];
+
+// Share of the original's words a decoded variant must contain to count as
+// the same instruction (models drop articles etc. when encoding)
+const MIN_DECODED_OVERLAP: f64 = 0.5;
+
+pub fn lookup(key: &str) -> Option<Check> {
+    VALIDATORS.iter().find(|(k, _)| *k == key).map(|(_, f)| *f)
+}
+
+pub fn registered_keys() -> impl Iterator<Item = &'static str> {
+    VALIDATORS.iter().map(|(k, _)| *k)
+}
+
+// One key of one record that failed its check
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub prompt_count: u32,
+    pub key: String,
+    pub reason: String,
+}
+
+// Check `keys` of one record; missing or empty keys are violations too
+pub fn check_record(
+    prompt_count: u32,
+    original: &str,
+    extra: &Map<String, Value>,
+    keys: &[String],
+) -> Vec<Violation> {
+    keys.iter()
+        .filter_map(|key| {
+            check_value(key, original, extra.get(key), true)
+                .err()
+                .map(|reason| Violation { prompt_count, key: key.clone(), reason })
+        })
+        .collect()
+}
+
+// Check one returned value; `run_checks == false` only requires a non-empty string
+pub fn check_value(key: &str, original: &str, value: Option<&Value>, run_checks: bool) -> Result<(), String> {
+    match value {
+        None => Err("missing".to_string()),
+        Some(Value::String(s)) if s.trim().is_empty() => Err("empty".to_string()),
+        Some(Value::String(s)) if run_checks => lookup(key).map_or(Ok(()), |f| f(original, s)),
+        Some(Value::String(_)) => Ok(()),
+        Some(_) => Err("not a string".to_string()),
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct KeyStats {
+    pub checked: usize,
+    pub failed: usize,
+}
+
+// Per-set validation report written next to the generator output
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub version_set: String,
+    pub records: usize,
+    pub records_failed: usize,
+    pub keys: BTreeMap<String, KeyStats>,
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn new(version_set: &str) -> Self {
+        Self {
+            version_set: version_set.to_string(),
+            records: 0,
+            records_failed: 0,
+            keys: BTreeMap::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, prompt_count: u32, original: &str, extra: &Map<String, Value>, keys: &[String]) {
+        let found = check_record(prompt_count, original, extra, keys);
+        self.records += 1;
+        if !found.is_empty() {
+            self.records_failed += 1;
+        }
+        for key in keys {
+            self.keys.entry(key.clone()).or_default().checked += 1;
+        }
+        for v in &found {
+            self.keys.entry(v.key.clone()).or_default().failed += 1;
+        }
+        self.violations.extend(found);
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+// ------------------------------- checks --------------------------------
+
+fn all_caps(_: &str, v: &str) -> Result<(), String> {
+    match v.chars().filter(|c| c.is_lowercase()).count() {
+        0 => Ok(()),
+        n => Err(format!("{n} lowercase letters")),
+    }
+}
+
+fn no_caps(_: &str, v: &str) -> Result<(), String> {
+    match v.chars().filter(|c| c.is_uppercase()).count() {
+        0 => Ok(()),
+        n => Err(format!("{n} uppercase letters")),
+    }
+}
+
+fn random_caps(_: &str, v: &str) -> Result<(), String> {
+    let words: Vec<&str> = v.split_whitespace().filter(|w| w.chars().any(char::is_alphabetic)).collect();
+    let mixed = words
+        .iter()
+        .filter(|w| w.chars().skip(1).any(char::is_uppercase) && w.chars().any(char::is_lowercase))
+        .count();
+    if mixed == 0 {
+        Err("no word with mixed inner capitalisation".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn base64(original: &str, v: &str) -> Result<(), String> {
+    let compact: String = v.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&compact)
+        .map_err(|e| format!("does not decode: {e}"))?;
+    let decoded = String::from_utf8(bytes).map_err(|_| "decodes to non-UTF-8 bytes".to_string())?;
+    decoded_matches(original, &decoded)
+}
+
+fn rot13_round_trip(original: &str, v: &str) -> Result<(), String> {
+    if overlap(original, v) >= MIN_DECODED_OVERLAP {
+        return Err("still readable without decoding".to_string());
+    }
+    let decoded: String = v.chars().map(rot13).collect();
+    decoded_matches(original, &decoded)
+}
+
+fn reversed_text(original: &str, v: &str) -> Result<(), String> {
+    let decoded: String = v.chars().rev().collect();
+    decoded_matches(original, &decoded)
+}
+
+fn emoji_only(_: &str, v: &str) -> Result<(), String> {
+    match v.chars().filter(|c| c.is_alphanumeric()).count() {
+        0 => Ok(()),
+        n => Err(format!("{n} letters or digits")),
+    }
+}
+
+fn fewest_words(original: &str, v: &str) -> Result<(), String> {
+    let (o, n) = (word_count(original), word_count(v));
+    // a one- or two-word original can only be matched, not beaten
+    if n < o || (o <= 2 && n <= o) {
+        Ok(())
+    } else {
+        Err(format!("{n} words, original has {o}"))
+    }
+}
+
+fn few_words(original: &str, v: &str) -> Result<(), String> {
+    let (o, n) = (word_count(original), word_count(v));
+    if n <= o {
+        Ok(())
+    } else {
+        Err(format!("{n} words, original has {o}"))
+    }
+}
+
+fn no_spaces(_: &str, v: &str) -> Result<(), String> {
+    match v.chars().filter(|c| *c == ' ').count() {
+        0 => Ok(()),
+        n => Err(format!("{n} spaces")),
+    }
+}
+
+fn no_punct(_: &str, v: &str) -> Result<(), String> {
+    match v.chars().filter(|c| is_punct(*c)).count() {
+        0 => Ok(()),
+        n => Err(format!("{n} punctuation marks")),
+    }
+}
+
+fn zero_width(_: &str, v: &str) -> Result<(), String> {
+    if v.chars().any(|c| matches!(c, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}')) {
+        Ok(())
+    } else {
+        Err("no zero-width characters".to_string())
+    }
+}
+
+fn zalgo(_: &str, v: &str) -> Result<(), String> {
+    if v.chars().any(|c| ('\u{0300}'..='\u{036F}').contains(&c)) {
+        Ok(())
+    } else {
+        Err("no combining marks".to_string())
+    }
+}
+
+fn leet_speak(original: &str, v: &str) -> Result<(), String> {
+    let digits = |s: &str| s.chars().filter(char::is_ascii_digit).count();
+    if digits(v) > digits(original) {
+        Ok(())
+    } else {
+        Err("no letters replaced by digits".to_string())
+    }
+}
+
+fn morse_code(_: &str, v: &str) -> Result<(), String> {
+    let stray: HashSet<char> = v
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '.' | '-' | '/' | '|' | '·' | '−'))
+        .collect();
+    if stray.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("non-Morse characters {:?}", stray.into_iter().collect::<String>()))
+    }
+}
+
+fn changed(original: &str, v: &str) -> Result<(), String> {
+    if v.trim() == original.trim() {
+        Err("identical to the original".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// -------------------------------- helpers --------------------------------
+
+fn decoded_matches(original: &str, decoded: &str) -> Result<(), String> {
+    let o = overlap(original, decoded);
+    if o >= MIN_DECODED_OVERLAP {
+        Ok(())
+    } else {
+        Err(format!("decoded text shares only {:.0}% of the original's words", o * 100.0))
+    }
+}
+
+fn words(s: &str) -> HashSet<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn word_count(s: &str) -> usize {
+    s.split_whitespace().count()
+}
+
+// Share of the original's distinct words found in `other`
+fn overlap(original: &str, other: &str) -> f64 {
+    let a = words(original);
+    if a.is_empty() {
+        return 1.0;
+    }
+    let b = words(other);
+    a.intersection(&b).count() as f64 / a.len() as f64
+}
@@ -0,0 +1,170 @@
+/*
+cargo taxonomy list
+cargo taxonomy list obstruction
+cargo taxonomy check
+cargo taxonomy export
+*/
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use rephras::taxonomy::{splice_categories, Taxonomy, DEFAULT_TAXONOMY};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+// Inspect the paraphrase taxonomy and keep the derived files in sync with it
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    #[arg(long, default_value = DEFAULT_TAXONOMY)]
+    taxonomy: PathBuf,
+
+    // key -> tags file derived from the taxonomy
+    #[arg(long, default_value = "a_data/paraphrases_tagged.json")]
+    tags_json: PathBuf,
+
+    // Markdown overview whose generated block lists every set
+    #[arg(long, default_value = "categories.md")]
+    categories: PathBuf,
+
+    // Dashboard script holding PARAPHRASE_FAMILIES
+    #[arg(long, default_value = "d_gui/script.js")]
+    gui_script: PathBuf,
+
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand, Debug)]
+enum Cmd {
+    // List sets, or the keys of one set
+    List { set: Option<String> },
+    // Fail if any derived file disagrees with the taxonomy
+    Check,
+    // Regenerate paraphrases_tagged.json and the categories.md block
+    Export,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let tax = Taxonomy::load(&cli.taxonomy)?;
+
+    match &cli.cmd {
+        Cmd::List { set: None } => {
+            for (name, set) in &tax.sets {
+                println!("{name:28} {:3} keys  {}", set.keys.len(), set.title);
+            }
+        }
+        Cmd::List { set: Some(name) } => {
+            let (_, set) = tax.resolve_set(name)?;
+            for key in &set.keys {
+                match tax.description(key) {
+                    Some(d) => println!("{key}  – {d}"),
+                    None => println!("{key}"),
+                }
+            }
+        }
+        Cmd::Export => {
+            fs::write(&cli.tags_json, tax.render_tags_json())
+                .with_context(|| format!("writing {}", cli.tags_json.display()))?;
+            let md = fs::read_to_string(&cli.categories)
+                .with_context(|| format!("reading {}", cli.categories.display()))?;
+            fs::write(&cli.categories, splice_categories(&md, &tax.render_categories_block()))
+                .with_context(|| format!("writing {}", cli.categories.display()))?;
+            println!(
+                "wrote {} and {}",
+                cli.tags_json.display(),
+                cli.categories.display()
+            );
+        }
+        Cmd::Check => check(&cli, &tax)?,
+    }
+    Ok(())
+}
+
+fn check(cli: &Cli, tax: &Taxonomy) -> Result<()> {
+    let mut problems: Vec<String> = Vec::new();
+
+    // paraphrases_tagged.json must be exactly what `export` would write
+    let tags_raw = fs::read_to_string(&cli.tags_json)
+        .with_context(|| format!("reading {}", cli.tags_json.display()))?;
+    let on_disk: serde_json::Value = serde_json::from_str(&tags_raw)
+        .with_context(|| format!("parsing {}", cli.tags_json.display()))?;
+    let expected: serde_json::Value = serde_json::from_str(&tax.render_tags_json())?;
+    if on_disk != expected {
+        problems.push(format!(
+            "{} is out of date – run `cargo taxonomy export`",
+            cli.tags_json.display()
+        ));
+    }
+
+    // categories.md generated block
+    let md = fs::read_to_string(&cli.categories)
+        .with_context(|| format!("reading {}", cli.categories.display()))?;
+    if splice_categories(&md, &tax.render_categories_block()) != md {
+        problems.push(format!(
+            "{} generated block is out of date – run `cargo taxonomy export`",
+            cli.categories.display()
+        ));
+    }
+
+    // PARAPHRASE_FAMILIES may only reference known keys
+    let js = fs::read_to_string(&cli.gui_script)
+        .with_context(|| format!("reading {}", cli.gui_script.display()))?;
+    let family_keys = paraphrase_families(&js)
+        .with_context(|| format!("no PARAPHRASE_FAMILIES in {}", cli.gui_script.display()))?;
+    let known: HashSet<&str> = tax.all_keys().into_iter().collect();
+    for key in &family_keys {
+        if key != "instruction_original" && !known.contains(key.as_str()) {
+            problems.push(format!(
+                "{}: PARAPHRASE_FAMILIES references unknown key {key}",
+                cli.gui_script.display()
+            ));
+        }
+    }
+
+    // not fatal: keys the dashboard will not group anywhere
+    let grouped: HashSet<&str> = family_keys.iter().map(String::as_str).collect();
+    let ungrouped: BTreeSet<&str> = tax
+        .sets
+        .values()
+        .flat_map(|s| s.keys.iter().map(String::as_str))
+        .filter(|k| !grouped.contains(k))
+        .collect();
+    if !ungrouped.is_empty() {
+        println!(
+            "note: {} set keys are not in any PARAPHRASE_FAMILIES group: {}",
+            ungrouped.len(),
+            ungrouped.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    if problems.is_empty() {
+        println!("taxonomy OK ({} sets, {} keys)", tax.sets.len(), known.len());
+        return Ok(());
+    }
+    for p in &problems {
+        eprintln!("✗ {p}");
+    }
+    bail!("{} taxonomy check(s) failed", problems.len());
+}
+
+// Every quoted identifier inside `const PARAPHRASE_FAMILIES = { ... };`
+fn paraphrase_families(js: &str) -> Option<Vec<String>> {
+    let start = js.find("const PARAPHRASE_FAMILIES")?;
+    let body = &js[start..];
+    let end = body.find("};")?;
+    let body = &body[..end];
+
+    let mut keys = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find('"') {
+        let after = &rest[open + 1..];
+        let close = after.find('"')?;
+        keys.push(after[..close].to_string());
+        rest = &after[close + 1..];
+    }
+    Some(keys)
+}
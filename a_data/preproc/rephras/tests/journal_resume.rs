@@ -0,0 +1,59 @@
+// Journal resume after a crash: a run killed mid-write leaves a torn last
+// line; every later `--resume` must still read the journal back.
+//
+//   cargo test --manifest-path a_data/preproc/rephras/Cargo.toml --test journal_resume
+
+use rephras::checkpoint::{read_journal, Journal};
+use serde_json::{json, Value};
+use std::{fs, io::Write};
+
+#[test]
+fn torn_journal_survives_two_resumes() {
+    let dir = std::env::temp_dir().join(format!("rephras_journal_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.json.journal.jsonl");
+
+    let mut journal = Journal::open(&path, false).unwrap();
+    journal.append(&json!({"prompt_count": 1})).unwrap();
+    journal.append(&json!({"prompt_count": 2})).unwrap();
+    drop(journal);
+    // killed in the middle of record 3
+    fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(br#"{"prompt_co"#).unwrap();
+
+    // first resume: the torn line is skipped, then dropped before appending
+    let done: Vec<Value> = read_journal(&path).unwrap();
+    assert_eq!(done.len(), 2);
+    let mut journal = Journal::open(&path, true).unwrap();
+    journal.append(&json!({"prompt_count": 3})).unwrap();
+    drop(journal);
+
+    // second resume: no corrupt line in the middle
+    let done: Vec<Value> = read_journal(&path).unwrap();
+    let counts: Vec<u64> = done.iter().map(|v| v["prompt_count"].as_u64().unwrap()).collect();
+    assert_eq!(counts, [1, 2, 3]);
+    let mut journal = Journal::open(&path, true).unwrap();
+    journal.append(&json!({"prompt_count": 4})).unwrap();
+    drop(journal);
+
+    let done: Vec<Value> = read_journal(&path).unwrap();
+    assert_eq!(done.len(), 4);
+    assert!(fs::read_to_string(&path).unwrap().ends_with("{\"prompt_count\":4}\n"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn journal_torn_before_its_first_newline_resumes_empty() {
+    let dir = std::env::temp_dir().join(format!("rephras_journal_first_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.json.journal.jsonl");
+    fs::write(&path, r#"{"prompt_count": 1, "instr"#).unwrap();
+
+    let mut journal = Journal::open(&path, true).unwrap();
+    journal.append(&json!({"prompt_count": 1})).unwrap();
+    drop(journal);
+    let done: Vec<Value> = read_journal(&path).unwrap();
+    assert_eq!(done.len(), 1);
+    let _ = fs::remove_dir_all(&dir);
+}
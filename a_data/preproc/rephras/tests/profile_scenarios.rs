@@ -0,0 +1,41 @@
+// The MMLU profile fills `scenarios` with the same rules as `cargo add_scenarios`.
+//
+//   cargo test --manifest-path a_data/preproc/rephras/Cargo.toml --test profile_scenarios
+
+use rephras::extract::Extractor;
+use rephras::profile::{Dataset, Profile};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+fn record(v: Value) -> Map<String, Value> {
+    v.as_object().unwrap().clone()
+}
+
+#[test]
+fn mmlu_profile_matches_add_scenarios() {
+    // the tail is copied verbatim, trailing newline included
+    let question = "For which of these two scenarios does the main character do something clearly morally wrong? \
+                    Scenario 1 | I took his wallet.\nScenario 2 | I returned it.\n";
+    let raw = record(json!({"question": question, "choices": ["a", "b", "c", "d"], "answer": 1}));
+    let (text, fields) = Profile::builtin(Dataset::Mmlu).take_source(raw).unwrap();
+    assert_eq!(text, question);
+
+    let rules = Extractor::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../../extract_rules/scenarios.toml")).unwrap();
+    let mut expected = record(json!({"instruction_original": question}));
+    assert_eq!(rules.apply(&mut expected), ["scenarios"]);
+    assert_eq!(fields["scenarios"], expected["scenarios"]);
+    assert_eq!(fields["scenarios"], "Scenario 1 | I took his wallet.\nScenario 2 | I returned it.\n");
+}
+
+#[test]
+fn mmlu_profile_keeps_existing_or_missing_scenarios() {
+    let profile = Profile::builtin(Dataset::Mmlu);
+    let raw = record(json!({"question": "Scenario 1 | x", "choices": [], "answer": 0, "scenarios": "given"}));
+    let (_, fields) = profile.take_source(raw).unwrap();
+    assert_eq!(fields["scenarios"], "given");
+
+    let raw = record(json!({"question": "What is 2 + 2?", "choices": [], "answer": 0}));
+    let (_, fields) = profile.take_source(raw).unwrap();
+    assert!(!fields.contains_key("scenarios"));
+    assert!(!fields.contains_key("instruction_original"));
+}
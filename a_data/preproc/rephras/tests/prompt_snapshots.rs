@@ -0,0 +1,140 @@
+// Prompt snapshots: render every request the generator would send for the
+// records in b_tests/phrx/*.json (generator `--dry-run`) and compare them with
+// the files committed under b_tests/phrx/snapshots/<input>/<set>/. Any change
+// to a prompt template, the taxonomy or a schema shows up as a failing diff.
+//
+// After an intended change, regenerate and review the snapshots with
+//   UPDATE_SNAPSHOTS=1 cargo test --manifest-path a_data/preproc/rephras/Cargo.toml
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Sets rendered per input; `extra` is split over calls, covering chunking,
+// `politeness` is a graded scale, `composed` renders the first model step of
+// each composition (once per key; a rule step first sends nothing)
+const SETS: &[(&str, &[&str])] = &[
+    ("style", &[]),
+    ("politeness", &[]),
+    ("obstruction", &[]),
+    ("language", &[]),
+    ("extra", &["--keys-per-call", "20"]),
+    (
+        "composed",
+        &[
+            "--compose",
+            "style:instruct_formal_academic,obstruction:instruct_typo_swap",
+            "--compose",
+            "style:instruct_formal_academic,syntax:instruct_rot13",
+            "--compose",
+            "obstruction:instruct_typo_swap,style:instruct_archaic",
+        ],
+    ),
+];
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../..")
+}
+
+fn files(dir: &Path) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|rd| rd.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default();
+    out.sort();
+    out
+}
+
+fn render(input: &Path, set: &str, extra: &[&str], out: &Path) {
+    let _ = fs::remove_dir_all(out);
+    let root = repo_root();
+    // the generator writes logs/ into its working directory
+    let work = out.with_extension("work");
+    fs::create_dir_all(&work).unwrap();
+    let run = Command::new(env!("CARGO_BIN_EXE_generate_paraphrases_skipfail"))
+        .current_dir(&work)
+        .arg("--dry-run")
+        .arg(out)
+        .arg("--version-set")
+        .arg(set)
+        .arg("--taxonomy")
+        .arg(root.join("a_data/paraphrase_taxonomy.toml"))
+        .args(extra)
+        .arg(input)
+        .arg(work.join("unused.json"))
+        .output()
+        .expect("failed to run the generator");
+    assert!(
+        run.status.success(),
+        "dry run of {} / {set} failed: {}",
+        input.display(),
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
+
+#[test]
+fn prompts_match_snapshots() {
+    let root = repo_root();
+    let inputs: Vec<PathBuf> = files(&root.join("b_tests/phrx"))
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    assert!(!inputs.is_empty(), "no inputs in b_tests/phrx");
+
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let scratch = std::env::temp_dir().join(format!("rephras-snapshots-{}", std::process::id()));
+    let mut mismatches = Vec::new();
+
+    for input in &inputs {
+        let stem = input.file_stem().unwrap().to_string_lossy().into_owned();
+        for (set, extra) in SETS {
+            let expected_dir = root.join("b_tests/phrx/snapshots").join(&stem).join(set);
+            let actual_dir = scratch.join(&stem).join(set);
+            render(input, set, extra, &actual_dir);
+
+            if update {
+                let _ = fs::remove_dir_all(&expected_dir);
+                fs::create_dir_all(&expected_dir).unwrap();
+                for f in files(&actual_dir) {
+                    fs::copy(&f, expected_dir.join(f.file_name().unwrap())).unwrap();
+                }
+                continue;
+            }
+
+            let names = |dir: &Path| -> Vec<String> {
+                files(dir)
+                    .iter()
+                    .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                    .collect()
+            };
+            if names(&expected_dir) != names(&actual_dir) {
+                mismatches.push(format!(
+                    "{stem}/{set}: files {:?}, snapshot has {:?}",
+                    names(&actual_dir),
+                    names(&expected_dir)
+                ));
+                continue;
+            }
+            for name in names(&actual_dir) {
+                let actual = fs::read_to_string(actual_dir.join(&name)).unwrap();
+                let expected = fs::read_to_string(expected_dir.join(&name)).unwrap();
+                if actual != expected {
+                    let line = actual
+                        .lines()
+                        .zip(expected.lines())
+                        .position(|(a, e)| a != e)
+                        .unwrap_or_else(|| actual.lines().count().min(expected.lines().count()));
+                    mismatches.push(format!("{stem}/{set}/{name}: differs from line {}", line + 1));
+                }
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&scratch);
+
+    assert!(
+        mismatches.is_empty(),
+        "prompts changed, review and rerun with UPDATE_SNAPSHOTS=1:\n{}",
+        mismatches.join("\n")
+    );
+}
@@ -12,12 +12,22 @@ cargo run --release --manifest-path a_data/preproc/alpaca_sanity/Cargo.toml -- \
   a_data/alpaca/alpaca_52k_clean.jsonl \
   a_data/alpaca/alpaca_52k_clean_pretty.jsonl \
   --pretty --keep-empty-output
+
+# also drop near-duplicates (Jaccard >= 0.6 on 5-char shingles), keep 500,
+# and list every cluster with its kept representative
+cargo run --release --manifest-path a_data/preproc/alpaca_sanity/Cargo.toml -- \
+  a_data/alpaca/alpaca_52k_clean.jsonl \
+  a_data/alpaca/alpaca_500_neardedup.jsonl \
+  --limit 500 --near-dup 0.6 --clusters a_data/alpaca/alpaca_500_clusters.json
 */
 
+mod minhash;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use minhash::{lsh_threshold, Index, Params};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader, Write},
     path::PathBuf,
@@ -37,6 +47,25 @@ struct Prompt {
     output: String,
 }
 
+// one near-duplicate cluster in the --clusters report
+#[derive(Debug, Serialize)]
+struct Cluster {
+    size: usize,
+    representative: ClusterEntry,
+    members: Vec<ClusterEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ClusterEntry {
+    prompt_count: Option<u64>,
+    prompt_id: Option<String>,
+    instruction: String,
+    input: String,
+    // similarity to the representative
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jaccard: Option<f64>,
+}
+
 fn main() -> Result<()> {
     // CLI
     let mut args = std::env::args().skip(1);
@@ -49,6 +78,11 @@ fn main() -> Result<()> {
     let mut pretty = false;
     let mut allow_empty_output = false;
     let mut limit_rows: Option<usize> = None;
+    let mut near_dup: Option<f64> = None; // Jaccard threshold
+    let mut shingle = 5usize;
+    let mut num_perm = 128usize;
+    let mut bands: Option<usize> = None;
+    let mut clusters_path: Option<String> = None;
     while let Some(flag) = args.next() {
         match flag.as_str() {
             "--pretty" => pretty = true,
@@ -59,10 +93,76 @@ fn main() -> Result<()> {
                     .ok_or_else(|| anyhow::anyhow!("--limit requires a number"))?;
                 limit_rows = Some(n.parse()?);
             }
+            "--near-dup" => {
+                let t: f64 = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--near-dup requires a Jaccard threshold"))?
+                    .parse()?;
+                if !(0.0..=1.0).contains(&t) {
+                    anyhow::bail!("--near-dup must be between 0 and 1");
+                }
+                near_dup = Some(t);
+            }
+            "--shingle" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--shingle requires a number"))?;
+                shingle = n.parse()?;
+                if shingle == 0 {
+                    anyhow::bail!("--shingle must be at least 1");
+                }
+            }
+            "--num-perm" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--num-perm requires a number"))?;
+                num_perm = n.parse()?;
+                if num_perm == 0 {
+                    anyhow::bail!("--num-perm must be at least 1");
+                }
+            }
+            "--bands" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--bands requires a number"))?;
+                bands = Some(n.parse()?);
+            }
+            "--clusters" => {
+                clusters_path = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow::anyhow!("--clusters requires a path"))?,
+                );
+            }
             _ => {}
         }
     }
 
+    // near-duplicate index, only with --near-dup
+    let mut index = match near_dup {
+        Some(threshold) => {
+            let params = match bands {
+                Some(b) => {
+                    if b == 0 || !num_perm.is_multiple_of(b) {
+                        anyhow::bail!("--bands must divide --num-perm ({num_perm})");
+                    }
+                    Params { threshold, shingle, num_perm, bands: b }
+                }
+                None => Params::with_auto_bands(threshold, shingle, num_perm),
+            };
+            println!(
+                "Near-dup: Jaccard >= {threshold}, {shingle}-char shingles, {} bands x {} rows (LSH threshold {:.2})",
+                params.bands,
+                params.rows(),
+                lsh_threshold(params.bands, params.rows())
+            );
+            Some(Index::new(params))
+        }
+        None => None,
+    };
+    // kept row -> near-duplicates dropped in its favour, with their similarity
+    let mut clusters: BTreeMap<usize, Vec<(Prompt, f64)>> = BTreeMap::new();
+    let mut near_duplicates = 0usize;
+
     // read
     let reader = BufReader::new(File::open(&in_path)?);
 
@@ -97,6 +197,16 @@ fn main() -> Result<()> {
             continue;
         }
 
+        // near-duplicate of a row already kept (index ids are `rows` positions)
+        if let Some(index) = index.as_mut() {
+            let text = format!("{} {}", row.instruction, row.input);
+            if let Some((kept, sim)) = index.insert_or_match(&text) {
+                near_duplicates += 1;
+                clusters.entry(kept).or_default().push((row, sim));
+                continue;
+            }
+        }
+
         // empty / non-empty counts & length stats
         for (name, val) in [
             ("instruction", &row.instruction),
@@ -125,6 +235,17 @@ fn main() -> Result<()> {
     println!("Unique rows           : {}", rows.len());
     println!("Duplicates skipped    : {duplicates}");
     println!("Dropped empty output  : {dropped_empty_output}");
+    if near_dup.is_some() {
+        println!("Near-duplicates       : {near_duplicates} in {} clusters", clusters.len());
+        let mut largest: Vec<(&usize, &Vec<(Prompt, f64)>)> = clusters.iter().collect();
+        largest.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+        for (kept, members) in largest.iter().take(5) {
+            println!("  {:>4} x {:?}", members.len() + 1, rows[**kept].instruction);
+            for (m, sim) in members.iter().take(3) {
+                println!("         {sim:.2} {:?}", m.instruction);
+            }
+        }
+    }
     println!("-- field empties -------------------");
     for k in ["instruction", "input", "output"] {
         println!(
@@ -143,6 +264,28 @@ fn main() -> Result<()> {
         }
     }
 
+    // cluster report: kept representative + the near-duplicates it replaced
+    if let Some(path) = &clusters_path {
+        let entry = |p: &Prompt, jaccard: Option<f64>| ClusterEntry {
+            prompt_count: p.prompt_count,
+            prompt_id: p.prompt_id.clone(),
+            instruction: p.instruction.clone(),
+            input: p.input.clone(),
+            jaccard: jaccard.map(|j| (j * 1000.0).round() / 1000.0),
+        };
+        let mut report: Vec<Cluster> = clusters
+            .iter()
+            .map(|(kept, members)| Cluster {
+                size: members.len() + 1,
+                representative: entry(&rows[*kept], None),
+                members: members.iter().map(|(m, sim)| entry(m, Some(*sim))).collect(),
+            })
+            .collect();
+        report.sort_by_key(|c| std::cmp::Reverse(c.size));
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("Near-dup clusters     -> {path}");
+    }
+
     // write cleaned copy
     let mut out = File::create(&out_path)?;
     for row in rows {
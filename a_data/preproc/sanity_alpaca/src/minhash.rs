@@ -0,0 +1,156 @@
+// Near-duplicate detection: MinHash signatures over character shingles,
+// banded into an LSH index so each new row is only compared with the kept
+// rows it shares a bucket with. Candidates are confirmed with the exact
+// Jaccard similarity of the shingle sets, so the index only decides what is
+// compared, never what counts as a duplicate.
+//
+// Rows are added in file order and the first row of a cluster stays its
+// representative, the same "first one wins" rule as the exact dedup.
+
+use std::collections::{BTreeSet, HashMap};
+
+// Number words folded to digits before shingling ("three tips" ~ "3 tips")
+const NUMBER_WORDS: [(&str, &str); 12] = [
+    ("zero", "0"), ("one", "1"), ("two", "2"), ("three", "3"), ("four", "4"), ("five", "5"),
+    ("six", "6"), ("seven", "7"), ("eight", "8"), ("nine", "9"), ("ten", "10"), ("twenty", "20"),
+];
+
+pub struct Params {
+    // minimum Jaccard similarity of the shingle sets
+    pub threshold: f64,
+    // characters per shingle
+    pub shingle: usize,
+    // signature length; split into `bands` bands of `num_perm / bands` rows
+    pub num_perm: usize,
+    pub bands: usize,
+}
+
+impl Params {
+    // The band count whose LSH threshold (1/b)^(1/r) lies closest to, but
+    // not above, the Jaccard threshold, so candidates err on recall
+    pub fn with_auto_bands(threshold: f64, shingle: usize, num_perm: usize) -> Self {
+        let bands = (1..=num_perm)
+            .filter(|&b| num_perm.is_multiple_of(b))
+            .filter(|&b| lsh_threshold(b, num_perm / b) <= threshold)
+            .min_by(|&a, &b| {
+                let da = threshold - lsh_threshold(a, num_perm / a);
+                let db = threshold - lsh_threshold(b, num_perm / b);
+                da.total_cmp(&db)
+            })
+            .unwrap_or(num_perm);
+        Self { threshold, shingle, num_perm, bands }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.num_perm / self.bands
+    }
+}
+
+pub fn lsh_threshold(bands: usize, rows: usize) -> f64 {
+    (1.0 / bands as f64).powf(1.0 / rows as f64)
+}
+
+// Lower-case, punctuation to spaces, number words to digits, single spaces
+pub fn normalise(text: &str) -> String {
+    let lowered: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    lowered
+        .split_whitespace()
+        .map(|w| NUMBER_WORDS.iter().find(|(word, _)| *word == w).map_or(w, |(_, digit)| *digit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Hashed character k-shingles; texts shorter than k are one shingle
+pub fn shingles(text: &str, k: usize) -> BTreeSet<u64> {
+    let chars: Vec<char> = normalise(text).chars().collect();
+    if chars.len() <= k {
+        return BTreeSet::from([fnv1a(&chars)]);
+    }
+    chars.windows(k).map(fnv1a).collect()
+}
+
+pub fn jaccard(a: &BTreeSet<u64>, b: &BTreeSet<u64>) -> f64 {
+    let inter = a.intersection(b).count();
+    let union = a.len() + b.len() - inter;
+    if union == 0 { 1.0 } else { inter as f64 / union as f64 }
+}
+
+// FNV-1a: stable across runs and Rust versions, unlike `DefaultHasher`
+fn fnv1a(chars: &[char]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for c in chars {
+        for b in (*c as u32).to_le_bytes() {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    h
+}
+
+// splitmix64 finaliser, seeded per permutation
+fn mix(x: u64, seed: u64) -> u64 {
+    let mut z = x.wrapping_add(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn signature(set: &BTreeSet<u64>, num_perm: usize) -> Vec<u64> {
+    (0..num_perm as u64)
+        .map(|seed| set.iter().map(|&x| mix(x, seed + 1)).min().unwrap_or(u64::MAX))
+        .collect()
+}
+
+pub struct Index {
+    params: Params,
+    // shingle set of every kept row, by insertion number
+    kept: Vec<BTreeSet<u64>>,
+    // (band, hash of the band's rows) -> kept rows in that bucket
+    buckets: HashMap<(usize, u64), Vec<usize>>,
+}
+
+impl Index {
+    pub fn new(params: Params) -> Self {
+        Self { params, kept: Vec::new(), buckets: HashMap::new() }
+    }
+
+    // The most similar kept row at or above the threshold, with its
+    // similarity; otherwise the text is kept and `None` returned
+    pub fn insert_or_match(&mut self, text: &str) -> Option<(usize, f64)> {
+        let set = shingles(text, self.params.shingle);
+        let sig = signature(&set, self.params.num_perm);
+        let band_keys: Vec<(usize, u64)> = sig
+            .chunks(self.params.rows())
+            .enumerate()
+            .map(|(band, rows)| (band, rows.iter().fold(0u64, |h, &v| mix(h ^ v, band as u64))))
+            .collect();
+
+        let mut candidates: Vec<usize> = band_keys
+            .iter()
+            .filter_map(|k| self.buckets.get(k))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let best = candidates
+            .into_iter()
+            .map(|i| (i, jaccard(&set, &self.kept[i])))
+            .filter(|&(_, sim)| sim >= self.params.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+        if best.is_some() {
+            return best;
+        }
+
+        let id = self.kept.len();
+        self.kept.push(set);
+        for key in band_keys {
+            self.buckets.entry(key).or_default().push(id);
+        }
+        None
+    }
+}
@@ -9,21 +9,75 @@ cargo run --manifest-path a_data/preproc/alpaca_sanity/Cargo.toml \
   --from 401 --to 500 \
   a_data/alpaca/alpaca_5k_proc.jsonl \
   a_data/alpaca/alpaca_100_slice5.json
+
+Stratified, seeded sample of 500 instead of a contiguous range
+(task verb x length x has-input; writes alpaca_500_strat.sampling.json):
+cargo run --manifest-path a_data/preproc/alpaca_sanity/Cargo.toml \
+  --bin sample_to_json -- \
+  --sample 500 --seed 42 \
+  a_data/alpaca/alpaca_5k_proc.jsonl \
+  a_data/alpaca/alpaca_500_strat.json
 */
 
 use anyhow::Result;
 use indexmap::IndexMap;
+use ingest::{
+    manifest::sha256_file,
+    sample::{append_run, length_bucket, now, print_strata, sampling_manifest_path, stratified, SamplingRun},
+};
 use serde_json::Value;
 use std::{
     env,
     fs::File,
     io::{BufRead, BufReader, Write},
+    path::Path,
 };
 
+// Heuristic task category from the leading verb / question word
+const TASK_VERBS: [(&str, &[&str]); 9] = [
+    ("generate", &["write", "compose", "create", "generate", "draft", "design", "invent", "construct", "come", "make", "develop"]),
+    ("rewrite", &["rewrite", "edit", "paraphrase", "rephrase", "convert", "translate", "change", "transform", "correct", "reword"]),
+    ("summarise", &["summarize", "summarise", "condense", "shorten"]),
+    ("classify", &["classify", "categorize", "categorise", "identify", "determine", "detect", "label", "decide", "select", "choose", "is", "are", "does"]),
+    ("explain", &["explain", "describe", "discuss", "outline", "elaborate", "define"]),
+    ("list", &["list", "name", "give", "provide", "suggest", "brainstorm", "recommend", "enumerate"]),
+    ("analyse", &["analyze", "analyse", "compare", "evaluate", "assess", "contrast", "critique"]),
+    ("compute", &["calculate", "compute", "solve", "find", "sort", "count", "estimate"]),
+    ("question", &["what", "who", "when", "where", "why", "how", "which", "can"]),
+];
+
+fn task_category(instruction: &str) -> &'static str {
+    let first = instruction
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    TASK_VERBS
+        .iter()
+        .find(|(_, verbs)| verbs.contains(&first.as_str()))
+        .map_or("other", |(category, _)| category)
+}
+
+// `<task>|<length>|<input|no_input>`
+fn stratum(v: &Value) -> String {
+    let field = |k: &str| v.get(k).and_then(Value::as_str).unwrap_or("");
+    let instruction = field("instruction");
+    let input = field("input");
+    format!(
+        "{}|{}|{}",
+        task_category(instruction),
+        length_bucket(&format!("{instruction} {input}")),
+        if input.trim().is_empty() { "no_input" } else { "input" }
+    )
+}
+
 fn main() -> Result<()> {
     // argument parsing
     let mut from: usize = 1;
     let mut to: usize = usize::MAX;
+    let mut sample: Option<usize> = None;
+    let mut seed: u64 = 42;
     let mut positional: Vec<String> = Vec::new();
 
     let mut args = env::args().skip(1);
@@ -31,6 +85,8 @@ fn main() -> Result<()> {
         match arg.as_str() {
             "--from" => from = args.next().expect("--from <N>").parse()?,
             "--to"   => to   = args.next().expect("--to <M>").parse()?,
+            "--sample" => sample = Some(args.next().expect("--sample <N>").parse()?),
+            "--seed" => seed = args.next().expect("--seed <S>").parse()?,
             _        => positional.push(arg),
         }
     }
@@ -44,6 +100,8 @@ fn main() -> Result<()> {
     // filter & collect
     let reader = BufReader::new(File::open(in_path)?);
     let mut out_vec: Vec<IndexMap<String, Value>> = Vec::new();
+    // rows in range, the stratum of each (only with --sample)
+    let mut keys: Vec<String> = Vec::new();
 
     for line in reader.lines() {
         let v: Value = serde_json::from_str(&line?)?;
@@ -72,6 +130,9 @@ fn main() -> Result<()> {
                 "prompt_id".to_string(),
                 v.get("prompt_id").cloned().unwrap_or(Value::Null),
             );
+            if sample.is_some() {
+                keys.push(stratum(&v));
+            }
             out_vec.push(obj);
         }
         if pc > to {
@@ -79,6 +140,34 @@ fn main() -> Result<()> {
         }
     }
 
+    // stratified sample of the rows in range, input order kept
+    if let Some(n) = sample {
+        let (picked, strata) = stratified(&keys, n, seed);
+        print_strata(&strata);
+        let mut rows: Vec<Option<IndexMap<String, Value>>> = out_vec.into_iter().map(Some).collect();
+        out_vec = picked.iter().filter_map(|&i| rows[i].take()).collect();
+
+        let manifest = sampling_manifest_path(Path::new(out_path));
+        append_run(
+            &manifest,
+            SamplingRun {
+                tool: "sample_to_json".to_string(),
+                created: now(),
+                input: in_path.clone(),
+                input_sha256: sha256_file(Path::new(in_path))?,
+                output: out_path.clone(),
+                seed,
+                strata_by: vec!["task".into(), "length".into(), "input".into()],
+                requested: n,
+                selected: out_vec.len(),
+                strata,
+                source_prompt_counts: out_vec.iter().map(|o| o["prompt_count"].clone()).collect(),
+                start_id: None,
+            },
+        )?;
+        println!("{} of {} rows sampled, manifest -> {}", out_vec.len(), keys.len(), manifest.display());
+    }
+
     // write pretty JSON array
     let mut out_file = File::create(out_path)?;
     writeln!(out_file, "{}", serde_json::to_string_pretty(&out_vec)?)?;
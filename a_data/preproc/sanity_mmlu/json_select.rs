@@ -6,6 +6,16 @@ cargo run \
     --output a_data/mmlu/global_facts_500.json \
     --count 100 \
     --start-id 401
+
+# seeded sample stratified by subject x length instead of the first 100
+cargo run \
+    --manifest-path a_data/preproc/sanity_mmlu/Cargo.toml \
+    --release -- \
+    --input a_data/mmlu/general_facts/world_religions.json \
+    --output a_data/mmlu/global_facts_500.json \
+    --count 100 \
+    --start-id 401 \
+    --stratified --seed 42
 */
 use std::{
     fs::File,
@@ -14,10 +24,15 @@ use std::{
 };
 
 use clap::Parser;
+use ingest::{
+    manifest::sha256_file,
+    sample::{append_run, length_bucket, now, print_strata, sampling_manifest_path, stratified, SamplingRun},
+};
 use serde::{Deserialize, Serialize};
 
-// Select the first N items from a JSON array, renumber `prompt_count`,
-// and append them to an output file (creating it if necessary)
+// Select the first N items (or a seeded stratified sample of N) from a JSON
+// array, renumber `prompt_count`, and append them to an output file
+// (creating it if necessary)
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Args {
@@ -36,6 +51,14 @@ struct Args {
     // Value to start `prompt_count` from
     #[arg(short = 's', long = "start-id")]
     start_id: usize,
+
+    // Draw a seeded stratified sample instead of the first N and record it in
+    // `<output>.sampling.json`
+    #[arg(long)]
+    stratified: bool,
+
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -48,6 +71,11 @@ struct Item {
     split: String,
 }
 
+// `<subject>|<length>`
+fn stratum(item: &Item) -> String {
+    format!("{}|{}", item.subject, length_bucket(&item.instruction_original))
+}
+
 fn main() -> anyhow::Result<()> {
     // Parse CLI
     let args = Args::parse();
@@ -57,9 +85,33 @@ fn main() -> anyhow::Result<()> {
     File::open(&args.input)?.read_to_string(&mut raw)?;
     let mut items: Vec<Item> = serde_json::from_str(&raw)?;
 
-    // Select and renumber
+    // Select (top N, or a stratified sample in input order) and renumber
     let keep = args.count.min(items.len());
-    let mut selected: Vec<Item> = items.drain(0..keep).collect();
+    let mut run: Option<SamplingRun> = None;
+    let mut selected: Vec<Item> = if args.stratified {
+        let keys: Vec<String> = items.iter().map(stratum).collect();
+        let (picked, strata) = stratified(&keys, keep, args.seed);
+        print_strata(&strata);
+        let mut slots: Vec<Option<Item>> = items.into_iter().map(Some).collect();
+        let selected: Vec<Item> = picked.iter().filter_map(|&i| slots[i].take()).collect();
+        run = Some(SamplingRun {
+            tool: "json_select (mmlu)".to_string(),
+            created: now(),
+            input: args.input.display().to_string(),
+            input_sha256: sha256_file(&args.input)?,
+            output: args.output.display().to_string(),
+            seed: args.seed,
+            strata_by: vec!["subject".into(), "length".into()],
+            requested: args.count,
+            selected: selected.len(),
+            strata,
+            source_prompt_counts: selected.iter().map(|i| i.prompt_count.into()).collect(),
+            start_id: Some(args.start_id),
+        });
+        selected
+    } else {
+        items.drain(0..keep).collect()
+    };
     for (i, item) in selected.iter_mut().enumerate() {
         item.prompt_count = args.start_id + i;
     }
@@ -84,6 +136,9 @@ fn main() -> anyhow::Result<()> {
     // Serialise and write back
     let pretty = serde_json::to_string_pretty(&combined)?;
     File::create(&args.output)?.write_all(pretty.as_bytes())?;
+    if let Some(run) = run {
+        append_run(&sampling_manifest_path(&args.output), run)?;
+    }
 
     println!(
         "Appended {keep} item(s) to {}, prompt_count starting at {}. \
@@ -0,0 +1,249 @@
+/*
+cargo cost_report
+
+# only the scoring runs of one sweep, own price table, also as JSON
+cargo cost_report \
+  usage/20250701-*_results_assess_*.jsonl \
+  --prices c_assess_inf/model_prices.toml \
+  --by model,set \
+  --json usage/report_20250701.json
+*/
+
+use anyhow::{bail, Context, Result};
+use c_assess_inf::usage::{Entry, DEFAULT_USAGE_DIR};
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_PRICES: &str = "c_assess_inf/model_prices.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Group {
+    Model,
+    Tool,
+    Dataset,
+    Set,
+}
+
+// Total tokens and cost of the usage ledgers
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    // Ledger files, or directories holding them
+    #[arg(default_value = DEFAULT_USAGE_DIR)]
+    ledgers: Vec<PathBuf>,
+
+    // Price table, USD per 1M tokens
+    #[arg(long, default_value = DEFAULT_PRICES)]
+    prices: PathBuf,
+
+    // Breakdowns to print (default: all of them)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    by: Vec<Group>,
+
+    // Also write the report as JSON
+    #[arg(long)]
+    json: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Price {
+    input: f64,
+    output: f64,
+    thinking: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceTable {
+    models: BTreeMap<String, Price>,
+}
+
+impl PriceTable {
+    fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read price table {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    // Exact entry, else the longest entry the model name starts with
+    fn lookup(&self, model: &str) -> Option<&Price> {
+        self.models.get(model).or_else(|| {
+            self.models
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, p)| p)
+        })
+    }
+
+    fn cost(&self, e: &Entry) -> Option<f64> {
+        let p = self.lookup(&e.model)?;
+        let u = &e.usage;
+        Some(
+            (u.prompt_tokens as f64 * p.input
+                + u.candidates_tokens as f64 * p.output
+                + u.thoughts_tokens as f64 * p.thinking.unwrap_or(p.output))
+                / 1e6,
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct Totals {
+    calls: u64,
+    prompt_tokens: u64,
+    candidates_tokens: u64,
+    thoughts_tokens: u64,
+    total_tokens: u64,
+    cost_usd: f64,
+    // calls whose model has no price; their tokens are counted, not costed
+    unpriced_calls: u64,
+}
+
+impl Totals {
+    fn add(&mut self, e: &Entry, cost: Option<f64>) {
+        self.calls += 1;
+        self.prompt_tokens += e.usage.prompt_tokens;
+        self.candidates_tokens += e.usage.candidates_tokens;
+        self.thoughts_tokens += e.usage.thoughts_tokens;
+        self.total_tokens += e.usage.total_tokens;
+        match cost {
+            Some(c) => self.cost_usd += c,
+            None => self.unpriced_calls += 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    total: Totals,
+    by: BTreeMap<Group, BTreeMap<String, Totals>>,
+    unpriced_models: BTreeSet<String>,
+}
+
+fn group_name(e: &Entry, g: Group) -> String {
+    let name = match g {
+        Group::Model => Some(&e.model),
+        Group::Tool => Some(&e.tool),
+        Group::Dataset => e.dataset.as_ref(),
+        Group::Set => e.version_set.as_ref(),
+    };
+    name.cloned().unwrap_or_else(|| "-".to_string())
+}
+
+// Every `*.jsonl` below the given paths
+fn ledger_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for p in paths {
+        if p.is_dir() {
+            for entry in fs::read_dir(p).with_context(|| format!("failed to list {}", p.display()))? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == "jsonl") {
+                    files.push(path);
+                }
+            }
+        } else if p.exists() {
+            files.push(p.clone());
+        } else {
+            bail!("{} does not exist", p.display());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let prices = PriceTable::load(&cli.prices)?;
+    let groups = if cli.by.is_empty() {
+        vec![Group::Model, Group::Tool, Group::Dataset, Group::Set]
+    } else {
+        cli.by.clone()
+    };
+
+    let files = ledger_files(&cli.ledgers)?;
+    if files.is_empty() {
+        bail!("no usage ledgers found");
+    }
+
+    let mut report = Report::default();
+    for file in &files {
+        let text = fs::read_to_string(file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            // a run killed mid-write leaves a torn last line
+            let entry: Entry = match serde_json::from_str(line) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("skipping {}:{}: {e}", file.display(), i + 1);
+                    continue;
+                }
+            };
+            let cost = prices.cost(&entry);
+            if cost.is_none() {
+                report.unpriced_models.insert(entry.model.clone());
+            }
+            report.total.add(&entry, cost);
+            for &g in &groups {
+                report
+                    .by
+                    .entry(g)
+                    .or_default()
+                    .entry(group_name(&entry, g))
+                    .or_default()
+                    .add(&entry, cost);
+            }
+        }
+    }
+
+    println!("{} ledgers, {} calls", files.len(), report.total.calls);
+    for (g, rows) in &report.by {
+        println!();
+        println!(
+            "{:40} {:>8} {:>14} {:>14} {:>14} {:>12}",
+            format!("by {g:?}").to_lowercase(),
+            "calls",
+            "prompt",
+            "output",
+            "total",
+            "cost USD"
+        );
+        for (name, t) in rows {
+            print_row(name, t);
+        }
+    }
+    println!();
+    print_row("TOTAL", &report.total);
+
+    if !report.unpriced_models.is_empty() {
+        println!(
+            "\nno price in {} for: {} – their tokens are not costed",
+            cli.prices.display(),
+            report.unpriced_models.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    if let Some(path) = &cli.json {
+        fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("report written to {}", path.display());
+    }
+    Ok(())
+}
+
+fn print_row(name: &str, t: &Totals) {
+    println!(
+        "{:40} {:>8} {:>14} {:>14} {:>14} {:>12.4}",
+        name,
+        t.calls,
+        t.prompt_tokens,
+        t.candidates_tokens + t.thoughts_tokens,
+        t.total_tokens,
+        t.cost_usd
+    );
+}
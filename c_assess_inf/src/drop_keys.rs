@@ -5,22 +5,23 @@ cargo drop_keys \
     output prompt_id
 */
 
-use std::fs::File;
-use std::io::{self, Read};
+use std::path::Path;
 use std::process;
 
+use anyhow::Result;
+use c_assess_inf::stream::{self, RecordWriter};
 use clap::Parser;
-use serde_json::{Value};
+use serde_json::Value;
 
-// Remove one or more keys from an array-of-objects JSON
+// Remove one or more keys from every record of a JSON array or JSONL file
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    // Optional path to the input JSON file (read stdin when omitted)
+    // Optional path to the input file (read stdin when omitted)
     #[arg(short, long)]
     input: Option<String>,
 
-    // Path to the output JSON file (overwritten if it exists)
+    // Path to the output file (overwritten if it exists; may be the input)
     #[arg(short, long)]
     output: String,
 
@@ -30,40 +31,27 @@ struct Args {
 
 fn main() {
     let args = Args::parse();
-
-    // Read input
-    let mut raw = String::new();
-    if let Some(path) = args.input {
-        File::open(&path)
-            .and_then(|mut f| f.read_to_string(&mut raw))
-            .unwrap_or_else(|e| exit_err(format!("Cannot read {path}: {e}")));
-    } else {
-        io::stdin()
-            .read_to_string(&mut raw)
-            .unwrap_or_else(|e| exit_err(format!("Cannot read <stdin>: {e}")));
+    if let Err(e) = run(&args) {
+        exit_err(format!("{e:#}"));
     }
+}
 
-    // Parse and mutate
-    let mut data: Value =
-        serde_json::from_str(&raw).unwrap_or_else(|e| exit_err(format!("Bad JSON: {e}")));
-
-    match &mut data {
-        Value::Array(arr) => {
-            for item in arr.iter_mut() {
-                if let Value::Object(obj) = item {
-                    for k in &args.keys {
-                        obj.remove(k);
-                    }
-                }
+// Records are streamed; the output only replaces its path once complete,
+// so `-i` and `-o` may name the same file
+fn run(args: &Args) -> Result<()> {
+    let input = Path::new(args.input.as_deref().unwrap_or("-"));
+    let mut out = RecordWriter::create(Path::new(&args.output))?;
+    for rec in stream::open(input)? {
+        let mut rec = rec?;
+        if let Value::Object(obj) = &mut rec {
+            for k in &args.keys {
+                obj.remove(k);
             }
         }
-        _ => exit_err("Top-level JSON must be an array".into()),
+        out.write(&rec)?;
     }
-
-    // Write output
-    File::create(&args.output)
-        .and_then(|mut f| serde_json::to_writer_pretty(&mut f, &data).map_err(|e| e.into()))
-        .unwrap_or_else(|e| exit_err(format!("Cannot write {}: {e}", &args.output)));
+    out.finish()?;
+    Ok(())
 }
 
 fn exit_err(msg: String) -> ! {
@@ -33,16 +33,17 @@ cargo instructmerge \
     c_assess_inf/output/mmlu/Qwen2.5-3B-Instruct/scores_500/voice.json
 */
 
-use std::{collections::{HashMap, HashSet}, fs, path::PathBuf};
+use std::{collections::{BTreeSet, HashMap}, path::PathBuf};
 use anyhow::{Context, Result};
+use c_assess_inf::stream::{self, Picked, RecordWriter};
 use clap::Parser;
 use serde_json::{Map, Value};
 
-// Merge instruction-tuning JSON files by prompt_count
+// Merge instruction-tuning JSON/JSONL files by prompt_count
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    // Output JSON file
+    // Output file (`.jsonl` writes JSONL)
     #[arg(short, long)]
     output: PathBuf,
 
@@ -50,7 +51,7 @@ struct Args {
     #[arg(short, long, required = true)]
     shared: Vec<String>,
 
-    // Input JSON files
+    // Input files (JSON arrays or JSONL)
     #[arg(required = true)]
     inputs: Vec<PathBuf>,
 }
@@ -58,38 +59,30 @@ struct Args {
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Read each file into a map: prompt_count ➜ object
-    let mut file_maps: Vec<HashMap<i64, Map<String, Value>>> = Vec::new();
+    // First pass: where each prompt_count sits in each file (position of
+    // the last occurrence) without keeping the records
+    let mut positions: Vec<HashMap<i64, usize>> = Vec::new();
 
     for path in &args.inputs {
-        let data = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read {}", path.display()))?;
-        let array: Vec<Value> = serde_json::from_str(&data)
-            .with_context(|| format!("{} is not valid JSON array", path.display()))?;
-
         let mut map = HashMap::new();
-        for obj in array {
-            let mut obj_map = obj.as_object().cloned().context("Expected JSON objects")?;
-            if !obj_map.contains_key("style") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    obj_map.insert("style".into(), Value::String(stem.to_string()));
-                }
-            }
-            let prompt_count = obj_map.get("prompt_count")
+        for (pos, obj) in stream::open(path)?.enumerate() {
+            let obj = obj?;
+            obj.as_object().context("Expected JSON objects")?;
+            let prompt_count = obj.get("prompt_count")
                 .and_then(Value::as_i64)
                 .context("`prompt_count` must be an integer")?;
 
-            if map.insert(prompt_count, obj_map).is_some() {
+            if map.insert(prompt_count, pos).is_some() {
                 eprintln!("Warning: duplicate prompt_count {} in {} — keeping last", prompt_count, path.display());
             }
         }
-        file_maps.push(map);
+        positions.push(map);
     }
 
     // Determine the set of prompt_count values common to *all* files
-    let mut common: Option<HashSet<i64>> = None;
-    for fmap in &file_maps {
-        let keys: HashSet<i64> = fmap.keys().copied().collect();
+    let mut common: Option<BTreeSet<i64>> = None;
+    for fmap in &positions {
+        let keys: BTreeSet<i64> = fmap.keys().copied().collect();
         common = match common {
             None => Some(keys),
             Some(acc) => Some(&acc & &keys),
@@ -98,7 +91,7 @@ fn main() -> Result<()> {
     let common = common.unwrap_or_default();
 
     // Log missing prompt_counts for each file
-    for (idx, fmap) in file_maps.iter().enumerate() {
+    for (idx, fmap) in positions.iter().enumerate() {
         for key in fmap.keys() {
             if !common.contains(key) {
                 eprintln!("File {} lacks prompt_count {} present in others; skipping.", args.inputs[idx].display(), key);
@@ -106,42 +99,52 @@ fn main() -> Result<()> {
         }
     }
 
-    // Merge entries
-    let mut merged: Vec<Value> = Vec::new();
+    // Second pass: files in prompt_count order are read in step, others
+    // have their common records loaded
+    let mut sources = Vec::with_capacity(args.inputs.len());
+    for (path, fmap) in args.inputs.iter().zip(positions) {
+        let picks = fmap.into_iter().filter(|(pc, _)| common.contains(pc)).map(|(pc, pos)| (pos, pc)).collect();
+        let source = Picked::open(path, picks)?;
+        if !source.is_streamed() {
+            eprintln!("Note: {} is not sorted by prompt_count; loading it", path.display());
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).map(str::to_string);
+        sources.push((source, stem));
+    }
+
+    // Merge entries, in ascending prompt_count for reproducibility
+    let mut out = RecordWriter::create(&args.output)?;
     for &pc in &common {
         let mut combined = Map::new();
         combined.insert("prompt_count".into(), Value::from(pc));
 
-        for fmap in &file_maps {
-            if let Some(obj) = fmap.get(&pc) {
-                for (k, v) in obj {
-                    // Insert shared keys only once; ensure consistency across files
-                    if args.shared.contains(k) {
-                        if let Some(prev) = combined.get(k) {
-                            if prev != v {
-                                eprintln!("Conflict on shared key '{}' for prompt_count {}: choosing first value", k, pc);
-                            }
-                        } else {
-                            combined.insert(k.clone(), v.clone());
+        for (source, stem) in &mut sources {
+            let Value::Object(mut obj) = source.take(pc)? else { unreachable!("checked in the first pass") };
+            if !obj.contains_key("style") {
+                if let Some(stem) = stem {
+                    obj.insert("style".into(), Value::String(stem.clone()));
+                }
+            }
+            for (k, v) in obj {
+                // Insert shared keys only once; ensure consistency across files
+                if args.shared.contains(&k) {
+                    if let Some(prev) = combined.get(&k) {
+                        if *prev != v {
+                            eprintln!("Conflict on shared key '{}' for prompt_count {}: choosing first value", k, pc);
                         }
                     } else {
-                        // Non-shared keys: later files can overwrite earlier keys if duplicate
-                        combined.insert(k.clone(), v.clone());
+                        combined.insert(k, v);
                     }
+                } else {
+                    // Non-shared keys: later files can overwrite earlier keys if duplicate
+                    combined.insert(k, v);
                 }
             }
         }
-        merged.push(Value::Object(combined));
+        out.write(&Value::Object(combined))?;
     }
+    let merged = out.finish()?;
 
-    // Stable sort by prompt_count for reproducibility
-    merged.sort_by_key(|v| v.get("prompt_count").and_then(Value::as_i64).unwrap_or(i64::MAX));
-
-    // Write output
-    let json_out = serde_json::to_string_pretty(&merged)?;
-    fs::write(&args.output, json_out)
-        .with_context(|| format!("Failed to write {}", args.output.display()))?;
-
-    println!("Merged {} prompt groups into {}", merged.len(), args.output.display());
+    println!("Merged {} prompt groups into {}", merged, args.output.display());
     Ok(())
 }
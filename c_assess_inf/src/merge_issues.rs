@@ -15,8 +15,8 @@
         c_assess_inf/output/alpaca/gemma-2-9b-it/scores_issues/style_slice2.issues.json
 */
 
-use std::{env, fs};
-use serde_json::{Value, json};
+use std::{env, path::Path};
+use c_assess_inf::stream::{self, RecordWriter};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Collect command‑line arguments, skipping the binary name
@@ -31,25 +31,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
-    let mut merged: Vec<Value> = Vec::new();
-
+    // Issues files are JSON arrays (or JSONL) of strings; stream them through
+    let mut out = RecordWriter::create(Path::new(&out_file))?;
     for path in args {
-        let data = fs::read_to_string(&path)
-            .map_err(|e| format!("Cannot read '{}': {}", path, e))?;
-        let json: Value = serde_json::from_str(&data)
-            .map_err(|e| format!("'{}' is not valid JSON: {}", path, e))?;
-
-        match json {
-            Value::Array(items) => merged.extend(items),
-            other => {
-                return Err(format!("'{}' must contain a top‑level JSON array, found {}", path, other).into());
-            }
+        for item in stream::open(Path::new(&path))? {
+            out.write(&item?)?;
         }
     }
+    let merged = out.finish()?;
 
-    let serialized = serde_json::to_string_pretty(&json!(merged))?;
-    fs::write(&out_file, serialized)?;
-
-    println!("✔ Merged {} items → {}", merged.len(), out_file);
+    println!("✔ Merged {} items → {}", merged, out_file);
     Ok(())
 }
@@ -17,18 +17,18 @@ cargo jsmerge \
     -o c_assess_inf/output/mmlu/gemma-2-9b-it/scores_500/voice.json
 */
 
-use std::fs;
 use std::path::PathBuf;
 
-use anyhow::{bail, Context, Result};
+use anyhow::Result;
+use c_assess_inf::stream::{self, RecordWriter};
 use clap::Parser;
-use serde_json::Value;
 
-// Merge JSON array files in the given order into one array file
+// Merge record files (JSON arrays or JSONL) in the given order into one file;
+// records are streamed, a `.jsonl` output is written as JSONL
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    // Paths to the input JSON files (each must be a top-level array)
+    // Paths to the input files (JSON arrays or JSONL)
     #[arg(required = true)]
     inputs: Vec<PathBuf>,
 
@@ -40,33 +40,19 @@ struct Args {
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Collect all items from each file
-    let mut merged_items = Vec::<Value>::new();
-
+    // Copy the records of each file through
+    let mut out = RecordWriter::create(&args.output)?;
     for path in &args.inputs {
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("reading {}", path.display()))?;
-        let json: Value = serde_json::from_str(&content)
-            .with_context(|| format!("parsing {}", path.display()))?;
-
-        match json {
-            Value::Array(arr) => merged_items.extend(arr),
-            other => bail!(
-                "File {} is not a JSON array (found {:?})",
-                path.display(),
-                other
-            ),
+        for rec in stream::open(path)? {
+            out.write(&rec?)?;
         }
     }
-
-    // Write combined array
-    let pretty = serde_json::to_string_pretty(&Value::Array(merged_items))?;
-    fs::write(&args.output, pretty)
-        .with_context(|| format!("writing {}", args.output.display()))?;
+    let written = out.finish()?;
 
     println!(
-        "Merged {} file(s) into {}",
+        "Merged {} file(s), {} record(s) into {}",
         args.inputs.len(),
+        written,
         args.output.display()
     );
     Ok(())
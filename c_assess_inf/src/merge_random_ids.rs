@@ -14,19 +14,19 @@ cargo merge_random_ids \
     
 */
 
-use std::{fs, path::{Path, PathBuf}};
+use std::path::{Path, PathBuf};
+use c_assess_inf::stream::{self, RecordWriter};
 use clap::{Arg, Command};
-use serde_json::Value;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // build the CLI
     let matches = Command::new("merge_random_ids")
         .version("1.0")
-        .about("Merge multiple JSON array files into one")
+        .about("Merge multiple JSON array or JSONL files into one")
         .arg(Arg::new("input")
             .short('i')
             .long("input")
-            .help("Input JSON file (array). Can be used multiple times.")
+            .help("Input file (JSON array or JSONL). Can be used multiple times.")
             .required(true)
             .num_args(1..) // 1 or more
         )
@@ -44,17 +44,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map(|s| s.as_str())
         .collect();
 
-    // read & merge
-    let mut merged: Vec<Value> = Vec::new();
-    for fname in &inputs {
-        let text = fs::read_to_string(fname)
-            .unwrap_or_else(|e| panic!("Failed to read {}: {}", fname, e));
-        let mut part: Vec<Value> = serde_json::from_str(&text)
-            .unwrap_or_else(|_| panic!("{} is not a JSON array", fname));
-        merged.append(&mut part);
-    }
-    println!("Collected {} objects from {} file(s)", merged.len(), inputs.len());
-
     // determine output path
     let out_path = if let Some(o) = matches.get_one::<String>("output") {
         PathBuf::from(o)
@@ -74,8 +63,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         parent.join(format!("{base}_merged.{ext}"))
     };
 
-    // write out
-    fs::write(&out_path, serde_json::to_string_pretty(&merged)?)?;
+    // stream the records through
+    let mut out = RecordWriter::create(&out_path)?;
+    for fname in &inputs {
+        for rec in stream::open(Path::new(fname))? {
+            out.write(&rec?)?;
+        }
+    }
+    let merged = out.finish()?;
+    println!("Collected {} objects from {} file(s)", merged, inputs.len());
     println!("Wrote merged file → {}", out_path.display());
 
     Ok(())
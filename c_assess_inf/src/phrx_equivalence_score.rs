@@ -6,12 +6,16 @@ cargo run --release -- \
   --api-key $GOOGLE_API_KEY
 */
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
+use c_assess_inf::cache::{self, CacheMode, DEFAULT_CACHE_DIR};
+use c_assess_inf::contracts::{self, Kind};
+use c_assess_inf::dryrun;
+use c_assess_inf::scrub::{self, DEFAULT_SCRUB_PATTERNS};
+use c_assess_inf::usage::{self, guess_dataset, guess_version_set, DEFAULT_USAGE_DIR};
 use chrono::Local;
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::header::HeaderMap;
-use serde::Deserialize;
+use paraphrx_core::{build_client, load_records, query_gemini, Logger, Query};
 use serde_json::{json, Map as JsonMap, Value};
 use std::{
     collections::{HashMap, HashSet},
@@ -40,31 +44,6 @@ fn get_model_limits(model_name: &str) -> ModelLimits {
     }
 }
 
-// Logger & Data Structures
-struct Logger {
-    writer: BufWriter<fs::File>,
-}
-impl Logger {
-    fn new<P: AsRef<Path>>(p: P) -> Result<Self> {
-        let file = fs::OpenOptions::new().create(true).append(true).write(true).open(p)?;
-        Ok(Self { writer: BufWriter::new(file) })
-    }
-    fn log(&mut self, msg: &str) {
-        let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
-        let _ = writeln!(self.writer, "[{ts}] {msg}");
-        let _ = self.writer.flush();
-    }
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct Record {
-    prompt_count: u32,
-    #[serde(alias = "instruction_original", alias = "instruction")]
-    instruction_original: String,
-    #[serde(flatten)]
-    extra: JsonMap<String, Value>,
-}
-
 // Command Line Interface
 #[derive(Parser, Debug)]
 #[command(version, author, about = "Assess semantic fidelity of paraphrases with dynamic chunking and resume support.")]
@@ -85,18 +64,42 @@ struct Cli {
     api_key: Option<String>,
     #[arg(long, default_value_t = 50)]
     max_paraphrases_per_chunk: usize,
-}
+    // Dataset and set recorded in the usage ledger; guessed from the prompts path when not given
+    #[arg(long)]
+    dataset: Option<String>,
+    #[arg(long)]
+    version_set: Option<String>,
+    // Directory of the per-run token usage ledgers (see `cargo cost_report`)
+    #[arg(long, default_value = DEFAULT_USAGE_DIR)]
+    usage_dir: PathBuf,
+    // Response cache: read (replay stored answers, store new ones) | write (refresh) | off
+    #[arg(long, value_enum, default_value_t = CacheMode::Read)]
+    cache: CacheMode,
+    #[arg(long, default_value = DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
+    // Write every scoring prompt to `<DIR>/<id>_<chunk>.txt` and print size
+    // statistics instead of calling Gemini; no results or statuses are saved
+    #[arg(long, value_name = "DIR")]
+    dry_run: Option<PathBuf>,
+    // Check the prompts and an existing output against their contracts (see
+    // `cargo validate`) before scoring
+    #[arg(long)]
+    validate_inputs: bool,
 
-// Core Functions (build_eval_prompt, query_gemini, etc. remain the same)
-fn read_records<P: AsRef<Path>>(p: P, logger: &mut Logger) -> Result<Vec<Record>> {
-    let raw = fs::read_to_string(&p).with_context(|| format!("cannot read {}", p.as_ref().display()))?;
-    let records: Vec<Record> = serde_json::from_str(&raw).context("JSON parse error")?;
-    if records.is_empty() {
-        logger.log("[warn] Prompts file contained 0 records.");
-    }
-    Ok(records)
+    // Mask PII and secrets (a_data/scrub_patterns.toml) in every prompt
+    // before it is sent; also applies to --dry-run
+    #[arg(long)]
+    scrub: bool,
+
+    #[arg(long, default_value = DEFAULT_SCRUB_PATTERNS)]
+    scrub_patterns: PathBuf,
+
+    // Placeholder -> original map and redaction counts (default `<output>.scrub.json`)
+    #[arg(long, value_name = "PATH")]
+    scrub_map: Option<PathBuf>,
 }
 
+// Equivalence prompt: the original and a batch of `(key, paraphrase)` to score 0-5
 fn build_eval_prompt(original: &str, batch: &[(String, String)]) -> (String, String) {
     let instructions = String::from(
 r#"You are an expert in linguistic semantics. Your task is to compare each provided "paraphrase" against the "Instruction original".
@@ -130,36 +133,6 @@ Instruction original:
     (full_prompt, full_original_text)
 }
 
-async fn query_gemini(client: &reqwest::Client, key: &str, model: &str, prompt: String) -> Result<JsonMap<String, Value>> {
-    let url = format!("https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}", model, key);
-    let body = json!({
-        "contents": [{"role": "user", "parts": [{"text": prompt}]}],
-        "generationConfig": { "responseMimeType": "application/json", "temperature": 0.0, "topP": 0.95 },
-        "safetySettings": [
-            {"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"},
-            {"category": "HARM_CATEGORY_HATE_SPEECH", "threshold": "BLOCK_NONE"},
-            {"category": "HARM_CATEGORY_SEXUALLY_EXPLICIT", "threshold": "BLOCK_NONE"},
-            {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_NONE"},
-        ]
-    });
-    let resp = client.post(url).json(&body).send().await?;
-    if !resp.status().is_success() {
-        return Err(anyhow!("{} — {}", resp.status(), resp.text().await?));
-    }
-    let raw: Value = resp.json().await?;
-    let text = raw["candidates"][0]["content"]["parts"][0]["text"].as_str().ok_or_else(|| anyhow!("Unexpected response structure"))?;
-    parse_response(text)
-}
-
-fn parse_response(s: &str) -> Result<JsonMap<String, Value>> {
-    let cleaned = s.trim().trim_start_matches("```json").trim_start_matches("```").trim_end_matches("```").trim();
-    if let Ok(v) = serde_json::from_str(cleaned) { return Ok(v); }
-    if let (Some(start), Some(end)) = (cleaned.find('{'), cleaned.rfind('}')) {
-        if let Ok(v) = serde_json::from_str(&cleaned[start..=end]) { return Ok(v); }
-    }
-    Err(anyhow!("Could not parse valid JSON from response: {}", s))
-}
-
 // ID status checkoff helpers
 fn load_status<P: AsRef<Path>>(path: P) -> Result<HashMap<u32,bool>> {
     if path.as_ref().exists() {
@@ -187,7 +160,12 @@ fn save_status<P: AsRef<Path>>(path: P, status: &HashMap<u32, bool>) -> anyhow::
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(dir) = &cli.dry_run {
+        dryrun::init(dir)?;
+    }
     let api_key = cli.api_key.or_else(|| std::env::var("GOOGLE_API_KEY").ok())
+        // a dry run sends nothing
+        .or_else(|| dryrun::global().map(|_| String::new()))
         .context("Missing Google API key. Provide it with --api-key or $GOOGLE_API_KEY")?;
     
     fs::create_dir_all("logs")?;
@@ -199,14 +177,37 @@ async fn main() -> Result<()> {
         stem.to_string_lossy(),
         cli.log_name,
         ts,
-    );g
+    );
 
     let log_path = PathBuf::from("logs").join(filename);
-    let mut logger = Logger::new(&log_path)?;
+    let mut logger = Logger::append(&log_path)?;
     logger.log(&format!("Script started. Model: {}", cli.model));
+    if cli.dry_run.is_none() {
+        let ledger = usage::init(
+            &cli.usage_dir,
+            "phrx_equivalence_score",
+            &stem.to_string_lossy(),
+            cli.dataset.clone().or_else(|| guess_dataset(&cli.prompts)).as_deref(),
+            cli.version_set.clone().or_else(|| guess_version_set(&cli.prompts)).as_deref(),
+        )?;
+        logger.log(&format!("Token usage ledger: {}", ledger.display()));
+    }
+    if cli.cache != CacheMode::Off {
+        cache::init(&cli.cache_dir, cli.cache)?;
+    }
+    if cli.scrub {
+        let map = cli.scrub_map.clone().unwrap_or_else(|| scrub::map_path_for(&cli.output));
+        let set = cli
+            .version_set
+            .clone()
+            .or_else(|| guess_version_set(&cli.prompts))
+            .unwrap_or_else(|| stem.to_string_lossy().into_owned());
+        scrub::init(&cli.scrub_patterns, &map, &set)?;
+        logger.log(&format!("[info] scrubbing prompts with {}", cli.scrub_patterns.display()));
+    }
 
-    let headers = HeaderMap::new();
-    let client = reqwest::Client::builder().default_headers(headers).timeout(Duration::from_secs(180)).build()?;
+    let client = build_client(Some(Duration::from_secs(180)))?;
+    let query = Query { temperature: Some(0.0), top_p: Some(0.95), no_safety_filters: true, ..Default::default() };
     let bpe = tiktoken_rs::p50k_base().unwrap();
     let model_limits = get_model_limits(&cli.model);
     let effective_token_limit = (model_limits.input as f64 * 0.5) as usize;
@@ -216,7 +217,17 @@ async fn main() -> Result<()> {
         .join(format!("id_status_{}.json", cli.log_name));
     let mut id_status = load_status(&status_file)
         .context("Unable to load or initialize id_status.json")?;
-    let input_records = read_records(&cli.prompts, &mut logger)?;
+    if cli.validate_inputs {
+        contracts::check_input(Kind::Paraphrases, &cli.prompts)?;
+        if cli.output.exists() {
+            contracts::check_input(Kind::Equivalence, &cli.output)?;
+        }
+        logger.log("[info] inputs match their contracts");
+    }
+    let input_records = load_records(&cli.prompts)?;
+    if input_records.is_empty() {
+        logger.log("[warn] Prompts file contained 0 records.");
+    }
 
     // Load existing results into a HashMap for efficient lookup
     let mut results_map: HashMap<u32, JsonMap<String, Value>> = if cli.output.exists() {
@@ -269,6 +280,7 @@ async fn main() -> Result<()> {
         logger.log(&format!("[info] ID {}: Found {} unscored paraphrases to process.", prompt_id, paraphrases_to_process.len()));
             
         let mut new_scores_for_this_id = JsonMap::new();
+        let mut chunk_no = 0;
 
         // Dynamic Chunking Loop (now operates on the unscored subset)
         while !paraphrases_to_process.is_empty() {
@@ -301,12 +313,18 @@ async fn main() -> Result<()> {
             paraphrases_to_process.drain(0..i);
             
             let (prompt, _) = build_eval_prompt(&record.instruction_original, &chunk_paraphrases);
+            let prompt = scrub::apply(prompt);
+            chunk_no += 1;
+            if let Some(d) = dryrun::global() {
+                d.write(&format!("{prompt_id}_{chunk_no}"), &prompt, &Value::Null)?;
+                continue;
+            }
             api_calls_made += 1;
             let mut success = false;
             
             for attempt in 1..=cli.max_attempts {
                 logger.log(&format!("[info] ID {}: Calling API for chunk of {} paraphrases (attempt {}/{})", prompt_id, chunk_paraphrases.len(), attempt, cli.max_attempts));
-                match query_gemini(&client, &api_key, &cli.model, prompt.clone()).await {
+                match query_gemini(&client, &api_key, &cli.model, &prompt, &query).await {
                     Ok(parsed_scores) => {
                         logger.log(&format!("[info] ID {}: API call SUCCEEDED on attempt {}", prompt_id, attempt));
                         new_scores_for_this_id.extend(parsed_scores);
@@ -328,6 +346,11 @@ async fn main() -> Result<()> {
             }
         }
         
+        if dryrun::global().is_some() {
+            pb.inc(1);
+            continue;
+        }
+
         // Merge new scores into the results map and save
         if !new_scores_for_this_id.is_empty() {
             let entry = results_map.entry(prompt_id).or_insert_with(|| {
@@ -356,6 +379,17 @@ async fn main() -> Result<()> {
     
     pb.finish_with_message("Processing complete");
     logger.log("RUN FINISHED");
+    if let Some(d) = dryrun::global() {
+        logger.log(&format!("Dry run: {}", d.summary()));
+        println!("dry run: {}", d.summary());
+    }
+    if let Some(c) = cache::global() {
+        logger.log(&format!("Response cache: {}", c.stats()));
+    }
+    for line in scrub::finish()? {
+        logger.log(&format!("Scrub: {line}"));
+        println!("scrub: {line}");
+    }
     if all_errors.is_empty() {
         logger.log("No fatal errors were recorded during the run.");
     } else {
@@ -13,16 +13,16 @@ cargo results_find_issues \
 use std::{
     collections::HashSet,
     fs,
-    io::Write,
     path::{Path, PathBuf},
 };
 
+use c_assess_inf::stream::{self, RecordWriter};
 use chrono::Local;
 use clap::Parser;
 use log::{error, info, LevelFilter};
 use simplelog::{Config, WriteLogger};
 
-// Scan JSON files for missing prompt_count values
+// Scan JSON/JSONL files for missing prompt_count values
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Cli {
@@ -66,7 +66,7 @@ fn main() {
         std::process::exit(1);
     }
 
-    // main loop over *.json files
+    // main loop over *.json / *.jsonl files
     let Ok(entries) = fs::read_dir(&cli.in_dir) else {
         error!("Cannot read input directory {:?}", cli.in_dir);
         std::process::exit(1);
@@ -76,7 +76,7 @@ fn main() {
         let path = entry.path();
 
         // skip non-JSON files
-        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+        if !stream::is_record_file(&path) {
             continue;
         }
 
@@ -102,12 +102,9 @@ fn main() {
 
 // Returns Ok(true) if an *_issues.json file was created.
 fn process_file(path: &Path, out_dir: &Path, last_prompt_count: u32) -> anyhow::Result<bool> {
-    let data = fs::read_to_string(path)?;
-    let json: serde_json::Value = serde_json::from_str(&data)?;
-    let arr = json.as_array().ok_or_else(|| anyhow::anyhow!("Top-level JSON is not an array"))?;
-
     let mut present: HashSet<u32> = HashSet::new();
-    for obj in arr {
+    for obj in stream::open(path)? {
+        let obj = obj?;
         if let Some(v) = obj.get("prompt_count") {
             if let Some(n) = v.as_u64()
                 .or_else(|| v.as_str().and_then(|s| s.parse::<u64>().ok()))
@@ -125,11 +122,6 @@ fn process_file(path: &Path, out_dir: &Path, last_prompt_count: u32) -> anyhow::
         return Ok(false);
     }
 
-    // Create issues vector in requested string format
-    let issues: Vec<String> = missing.drain(..)
-        .map(|n| format!("id {n}: missing"))
-        .collect();
-
     // Build output filename: <stem>_issues.json
     let mut out_path = out_dir.join(path.file_stem().unwrap());
     out_path.set_file_name(format!(
@@ -138,8 +130,12 @@ fn process_file(path: &Path, out_dir: &Path, last_prompt_count: u32) -> anyhow::
     ));
     out_path.set_extension("json");
 
-    let json_text = serde_json::to_string_pretty(&issues)?;
-    fs::File::create(&out_path)?.write_all(json_text.as_bytes())?;
+    // Write issues in the requested string format
+    let mut out = RecordWriter::create(&out_path)?;
+    for n in missing.drain(..) {
+        out.write(&format!("id {n}: missing"))?;
+    }
+    out.finish()?;
 
     Ok(true)
 }
@@ -9,17 +9,21 @@ cargo results_patch \
   --api-key "$GOOGLE_API_KEY"
 */
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
+use c_assess_inf::cache::{self, CacheMode, DEFAULT_CACHE_DIR};
+use c_assess_inf::contracts::{self, Kind};
+use c_assess_inf::dryrun;
+use c_assess_inf::scrub::{self, DEFAULT_SCRUB_PATTERNS};
+use c_assess_inf::usage::{self, guess_dataset, DEFAULT_USAGE_DIR};
 use clap::Parser;
+use paraphrx_core::gemini::Client;
+use paraphrx_core::{build_client, build_eval_prompt, query_gemini, read_records, schema_for_keys, Logger, Query, Record};
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Map as JsonMap, Value};
+use serde_json::{Map as JsonMap, Value};
 use chrono::Local;
 use std::{
     collections::HashMap,
     fs,
-    io::{BufWriter, Write},
     path::{Path, PathBuf},
     time::{Duration, SystemTime},
 };
@@ -29,31 +33,11 @@ use std::ffi::OsStr;
 use std::process;
 //use std::borrow::Cow;
 
-// tiny rolling logger
-struct Logger {
-    writer: BufWriter<fs::File>,
-}
-impl Logger {
-    fn new<P: AsRef<Path>>(p: P) -> Result<Self> {
-        let file = fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(p)?;
-        Ok(Self { writer: BufWriter::new(file) })
-    }
-    fn log(&mut self, msg: &str) {
-        let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
-        let _ = writeln!(self.writer, "[{ts}] {msg}");
-        let _ = self.writer.flush();
-    }
-}
-
 // Re-score only the prompt-IDs that appear in <issues-dir>/<TYPE>_issues.json
 async fn process_set(
     typ: &str,
     cli: &Cli,
-    client: &reqwest::Client,
+    client: &Client,
     api_key: &str,
     root_log: &mut Logger,
 ) -> Result<()> {
@@ -72,6 +56,17 @@ async fn process_set(
     let mut logger = Logger::new(&repair_log_path)?;
     logger.log("loading files");
 
+    if cli.validate_inputs {
+        contracts::check_input(Kind::Paraphrases, &instr_path)?;
+        contracts::check_input(Kind::Answers, &ans_path)?;
+        for (kind, path) in [(Kind::Scores, &score_path), (Kind::Issues, &issues_path)] {
+            if path.exists() {
+                contracts::check_input(kind, path)?;
+            }
+        }
+        logger.log("inputs match their contracts");
+    }
+
     let instr_map = read_records(&instr_path, &mut logger);
     let ans_map   = read_records(&ans_path,   &mut logger);
 
@@ -113,6 +108,7 @@ async fn process_set(
     for id in &todo_ids {
         if let Some(instr_rec) = instr_map.get(id) {
             process_single(
+                typ,
                 id,
                 instr_rec,
                 &ans_map,
@@ -131,11 +127,15 @@ async fn process_set(
         }
 
         bar.inc(1);
-        if cli.delay_ms > 0 {
+        if cli.delay_ms > 0 && dryrun::global().is_none() {
             sleep(Duration::from_millis(cli.delay_ms)).await;
         }
     }
     bar.finish();
+    if dryrun::global().is_some() {
+        root_log.log(&format!("set '{typ}' rendered, nothing patched"));
+        return Ok(());
+    }
 
     // dump unresolved issues without touching the original file
     let issues_patched_path =
@@ -181,18 +181,6 @@ async fn process_set(
     Ok(())
 }
 
-// data structs
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct Record {
-    prompt_count: u32,
-    #[serde(alias = "instruction", alias = "instruction_original")]
-    instruction_original: String,
-    #[serde(default)]
-    output: Option<String>,
-    #[serde(flatten)]
-    extra: JsonMap<String, Value>,
-}
-
 #[derive(Parser, Debug)]
 #[command(
     version,
@@ -232,46 +220,62 @@ struct Cli {
 
     #[arg(long = "api-key", value_name = "KEY")]
     api_key: Option<String>,
-}
 
-fn schema_for_keys(keys: &[String]) -> Value {
-    let mut props = JsonMap::new();
-    for k in keys {
-        props.insert(k.clone(), json!({"type":"array","items":{"type":"integer"},"minItems":10,"maxItems":10}));
-    }
-    json!({"type":"object","properties":props,"required":keys})
-}
+    // Dataset recorded in the usage ledger; guessed from --instructions-dir when not given
+    #[arg(long)]
+    dataset: Option<String>,
 
-// fault-tolerant JSON loader
-fn read_records(path: &Path, logger: &mut Logger) -> HashMap<String, Record> {
-    match fs::read_to_string(path)
-        .and_then(|s| serde_json::from_str::<Vec<Record>>(&s).map_err(Into::into))
-    {
-        Ok(vec) => vec.into_iter()
-                      .map(|r| (r.prompt_count.to_string(), r))
-                      .collect(),
-        Err(e) => {
-            logger.log(&format!(
-                "[fatal-but-skipped] could not parse {}: {e}", path.display()
-            ));
-            HashMap::new()                      // empty -> loop simply skips
-        }
-    }
-}
+    // Directory of the per-run token usage ledgers (see `cargo cost_report`)
+    #[arg(long, default_value = DEFAULT_USAGE_DIR)]
+    usage_dir: PathBuf,
+
+    // Response cache: read (replay stored answers, store new ones) | write (refresh) | off
+    #[arg(long, value_enum, default_value_t = CacheMode::Read)]
+    cache: CacheMode,
+
+    #[arg(long, default_value = DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
 
-const ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
+    // Write every judge request (prompt + schema) to `<DIR>/<TYPE>_<id>.txt`
+    // and print size statistics instead of calling Gemini; no files are patched
+    #[arg(long, value_name = "DIR")]
+    dry_run: Option<PathBuf>,
+
+    // Check each set's instructions, answers, scores and issues against their
+    // contracts (see `cargo validate`) before patching it
+    #[arg(long)]
+    validate_inputs: bool,
+
+    // Mask PII and secrets (a_data/scrub_patterns.toml) in every judge
+    // prompt before it is sent; also applies to --dry-run
+    #[arg(long)]
+    scrub: bool,
+
+    #[arg(long, default_value = DEFAULT_SCRUB_PATTERNS)]
+    scrub_patterns: PathBuf,
+
+    // Placeholder -> original map and redaction counts per set
+    // (default `<SCORES_DIR>/scrub.json`)
+    #[arg(long, value_name = "PATH")]
+    scrub_map: Option<PathBuf>,
+}
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(dir) = &cli.dry_run {
+        dryrun::init(dir)?;
+    }
 
     // common setup (client + root log)
     let api_key = cli
         .api_key
         .clone()
         .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
+        // a dry run sends nothing
+        .or_else(|| dryrun::global().map(|_| String::new()))
         .context("provide --api-key or set GOOGLE_API_KEY")?;
-    let client  = build_client()?;
+    let client  = build_client(None)?;
 
     let log_dir = Path::new("logs");
     fs::create_dir_all(log_dir)?;
@@ -282,9 +286,29 @@ async fn main() -> Result<()> {
         cli.model, cli.types
     ));
 
+    if cli.dry_run.is_none() {
+        let ledger = usage::init(
+            &cli.usage_dir,
+            "results_patch",
+            &cli.types.join("+"),
+            cli.dataset.clone().or_else(|| guess_dataset(&cli.instructions_dir)).as_deref(),
+            None,
+        )?;
+        root_logger.log(&format!("usage ledger {}", ledger.display()));
+    }
+    if cli.cache != CacheMode::Off {
+        cache::init(&cli.cache_dir, cli.cache)?;
+    }
+    if cli.scrub {
+        let map = cli.scrub_map.clone().unwrap_or_else(|| cli.scores_dir.join("scrub.json"));
+        scrub::init(&cli.scrub_patterns, &map, &cli.types.join("+"))?;
+        root_logger.log(&format!("scrubbing prompts with {}", cli.scrub_patterns.display()));
+    }
+
     // process every <TYPE> that the user passed in
     for t in &cli.types {
         root_logger.log(&format!("── set '{t}' ──"));
+        usage::set_version_set(t);
         if let Err(e) = process_set(
             t,
             &cli,
@@ -298,16 +322,28 @@ async fn main() -> Result<()> {
         }
     }
 
+    if let Some(c) = cache::global() {
+        root_logger.log(&format!("response cache: {}", c.stats()));
+        println!("response cache: {}", c.stats());
+    }
+    if let Some(d) = dryrun::global() {
+        root_logger.log(&format!("dry run: {}", d.summary()));
+        println!("dry run: {}", d.summary());
+    }
+    for line in scrub::finish()? {
+        root_logger.log(&format!("scrub: {line}"));
+        println!("scrub: {line}");
+    }
     println!("all done - see log files in {}", log_dir.display());
     Ok(())
 }
 
-
 async fn process_single(
+    typ: &str,
     id: &str,
     inst: &Record,
     ans_map: &HashMap<String, Record>,
-    client: &reqwest::Client,
+    client: &Client,
     api_key: &str,
     model: &str,
     max_attempts: u8,
@@ -356,7 +392,11 @@ async fn process_single(
     }
 
     let schema = schema_for_keys(&keys);
-    let prompt = build_eval_prompt(&section);
+    let prompt = scrub::apply_to(typ, build_eval_prompt(&section));
+    if let Some(d) = dryrun::global() {
+        d.write(&format!("{typ}_{id}"), &prompt, &schema)?;
+        return Ok(0);
+    }
     let mut success = false;
     let mut eval_json = JsonMap::new();
     let mut attempts_used = max_attempts;
@@ -365,7 +405,7 @@ async fn process_single(
             "[call] id {id} attempt {attempt}/{max_attempts}"
         ));
 
-        match query_gemini(client, api_key, model, schema.clone(), prompt.clone()).await {
+        match query_gemini(client, api_key, model, &prompt, &Query::with_schema(schema.clone())).await {
             Ok(obj) => {
                 logger.log(&format!(
                     "[ok]   id {id} attempt {attempt}/{max_attempts}"
@@ -415,57 +455,3 @@ async fn process_single(
     logger.log(&format!("[done] id {id} fully processed"));
     Ok(attempts_used)
 }
-
-fn build_client() -> Result<reqwest::Client> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    Ok(reqwest::Client::builder().default_headers(headers).build()?)
-}
-
-fn build_eval_prompt(section: &str) -> String {format!(r#"You are an expert evaluator.
-
-For every answer below, assess it against **ten metrics**. Each metric must be scored on a 0-10 integer scale (higher is better).
-
-Metrics (use **exact** order):
-1. Task Fulfilment / Relevance - Does it respond to every part of the prompt? Did it wander off-topic or over-answer?
-2. Usefulness & Actionability - Does it translate abstract ideas into concrete advice, examples, or next steps?
-3. Factual Accuracy & Verifiabiliy - Are the statements factually correct (no hallucinations)? If it cites sources or internal steps, do those match the final claims?
-4. Efficiency / Depth & Completeness - Does it avoid unnecessary verbosity or excessive brevity? Does it cover the key angles, edge-cases, and typical follow-ups? Could the user act on it without having to ask “what about X?”
-5. Reasoning Quality / Transparency - Are the steps implicitly or explicitly sound? If uncertain, does it flag that uncertainty instead of bluffing?
-6. Tone & Likeability - Is the style friendly and respectful, matching the user’s vibe? Would you enjoy a longer conversation in this voice?
-7. Adaptation to Context - Does it use any relevant info the user has shared (location, preferences, prior messages) appropriately?
-8. Safety & Bias Avoidance - Does it steer clear of harmful or disallowed content? Does it acknowledge and mitigate possible bias?
-9. Structure & Formatting & UX Extras - Is the writing logically ordered and easy to skim? Are lists, code blocks, tables, or rich widgets used when, but only when they genuinely improve readability or utility?
-10. Creativity - Does the answer make clever, non-obvious connections that you wouldn’t get from a quick Google search? Or, does it remix ideas, metaphors, or examples in a fresh way rather than serving boilerplate?
-
-Return **only** JSON conforming to this Pydantic model (nothing else, no code fences):
-
-```python
-from typing import Dict, List
-from pydantic import BaseModel, conlist
-
-class EvalResult(BaseModel):
-    __root__: Dict[str, conlist(int, min_items=10, max_items=10)]
-```
-
-Begin data to evaluate:
-
-{section}
-"#)}
-
-async fn query_gemini(
-    client:&reqwest::Client,
-    key:&str,
-    model:&str,
-    schema:Value,
-    prompt:String,
-)->Result<JsonMap<String,Value>>{
-    //let url=format!("{ENDPOINT}/models/{}/generateContent?key={}",model,key);
-    let url = format!("{ENDPOINT}/models/{}:generateContent?key={}", model, key);
-    let body=json!({"contents":[{"role":"user","parts":[{"text":prompt}]}],"generationConfig":{"responseMimeType":"application/json","responseSchema":schema}});
-    let resp=client.post(&url).json(&body).send().await?;
-    if !resp.status().is_success(){return Err(anyhow!("{} — {}",resp.status(),resp.text().await?));}
-    let resp_json:Value=resp.json().await?;
-    let json_text=resp_json["candidates"][0]["content"]["parts"][0]["text"].as_str().ok_or_else(||anyhow!("unexpected response structure"))?;
-    Ok(serde_json::from_str(json_text.trim())?)
-}
@@ -6,13 +6,15 @@ cargo sort_merge_ids \
 */
 
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs::{self, File},
     io::Write,
+    path::Path,
 };
 
 use anyhow::{Context, Result};
 use chrono::Local;
+use c_assess_inf::stream::{self, Picked, RecordWriter};
 use clap::Parser;
 use serde_json::{Map, Value};
 
@@ -42,9 +44,9 @@ fn get_id(obj: &Map<String, Value>) -> Option<i64> {
 struct Args {
     // Output file (will be created / overwritten)
     output_file: String,
-    // First input JSON file (array of objects)
+    // First input file (JSON array or JSONL of objects)
     input_a: String,
-    // Second input JSON file (array of objects)
+    // Second input file (JSON array or JSONL of objects)
     input_b: String,
 }
 
@@ -60,77 +62,74 @@ fn main() -> Result<()> {
     let mut log = File::create(&log_name).context("could not create log file")?;
     writeln!(log, "=== json_prompt_merger started at {} ===", Local::now())?;
 
-    // Read & parse both source files
-    let read_json = |path: &str| -> Result<Vec<Value>> {
-        let raw = fs::read_to_string(path).with_context(|| format!("reading {path}"))?;
-        serde_json::from_str(&raw).with_context(|| format!("parsing {path} as JSON array"))
-    };
-
-    let vec_a = read_json(&args.input_a)?;
-    writeln!(log, "Loaded {} objects from {}", vec_a.len(), args.input_a)?;
-
-    let vec_b = read_json(&args.input_b)?;
-    writeln!(log, "Loaded {} objects from {}", vec_b.len(), args.input_b)?;
-
-    // Merge into a BTreeMap (keeps ascending order)
-    let mut merged: BTreeMap<i64, Value> = BTreeMap::new();
+    // First pass: decide the winner of every id from the key counts alone;
+    // id -> (input, position in that input, key count)
+    let inputs = [&args.input_a, &args.input_b];
+    let mut merged: BTreeMap<i64, (usize, usize, usize)> = BTreeMap::new();
     let mut duplicates_seen: BTreeSet<i64> = BTreeSet::new();
 
-    let mut insert_object = |obj: Value, source: &str| -> Result<()> {
-        if let Some(map) = obj.as_object() {
-            if let Some(id) = get_id(map) {
-                match merged.get(&id) {
-                    None => {
-                        merged.insert(id, obj);
-                        writeln!(log, " -> added  id={id} from {source}")?;
-                    }
-                    Some(existing) => {
-                        duplicates_seen.insert(id);
-                        let existing_keys = key_count(existing);
-                        let new_keys = key_count(&obj);
-                        if new_keys > existing_keys {
-                            merged.insert(id, obj);
-                            writeln!(
-                                log,
-                                " -> duplicate id={id}: REPLACED ({}→{} keys) using {source}",
-                                existing_keys, new_keys
-                            )?;
-                        } else {
-                            writeln!(
-                                log,
-                                " -> duplicate id={id}: kept existing ({} keys), \
-                                 discarded {}-key object from {source}",
-                                existing_keys, new_keys
-                            )?;
-                        }
+    for (src, source) in inputs.iter().enumerate() {
+        let mut loaded = 0;
+        for (pos, obj) in stream::open(Path::new(source))?.enumerate() {
+            let obj = obj?;
+            loaded += 1;
+            let Some(map) = obj.as_object() else {
+                writeln!(log, " !! skipped non-object element in {source}")?;
+                continue;
+            };
+            let Some(id) = get_id(map) else {
+                writeln!(log, " !! skipped object with missing/invalid prompt_count in {source}")?;
+                continue;
+            };
+            let new_keys = key_count(&obj);
+            match merged.get(&id) {
+                None => {
+                    merged.insert(id, (src, pos, new_keys));
+                    writeln!(log, " -> added  id={id} from {source}")?;
+                }
+                Some(&(_, _, existing_keys)) => {
+                    duplicates_seen.insert(id);
+                    if new_keys > existing_keys {
+                        merged.insert(id, (src, pos, new_keys));
+                        writeln!(
+                            log,
+                            " -> duplicate id={id}: REPLACED ({}→{} keys) using {source}",
+                            existing_keys, new_keys
+                        )?;
+                    } else {
+                        writeln!(
+                            log,
+                            " -> duplicate id={id}: kept existing ({} keys), \
+                             discarded {}-key object from {source}",
+                            existing_keys, new_keys
+                        )?;
                     }
                 }
-            } else {
-                writeln!(log, " !! skipped object with missing/invalid prompt_count in {source}")?;
             }
-        } else {
-            writeln!(log, " !! skipped non-object element in {source}")?;
         }
-        Ok(())
-    };
+        writeln!(log, "Loaded {} objects from {}", loaded, source)?;
+    }
 
-    for v in vec_a {
-        insert_object(v, &args.input_a)?;
+    // Second pass: copy the winners in ascending id order
+    let mut picks: [HashMap<usize, i64>; 2] = Default::default();
+    for (&id, &(src, pos, _)) in &merged {
+        picks[src].insert(pos, id);
     }
-    for v in vec_b {
-        insert_object(v, &args.input_b)?;
+    let [picks_a, picks_b] = picks;
+    let mut sources = [
+        Picked::open(Path::new(&args.input_a), picks_a)?,
+        Picked::open(Path::new(&args.input_b), picks_b)?,
+    ];
+
+    let mut out = RecordWriter::create(Path::new(&args.output_file))?;
+    for (&id, &(src, _, _)) in &merged {
+        out.write(&sources[src].take(id)?)?;
     }
-
-    // Write merged output
-    let mut out = File::create(&args.output_file)
-        .with_context(|| format!("creating {}", args.output_file))?;
-    let ordered: Vec<&Value> = merged.values().collect();
-    serde_json::to_writer_pretty(&mut out, &ordered)
-        .with_context(|| format!("writing {}", args.output_file))?;
+    let written = out.finish()?;
     writeln!(
         log,
         "\nWrote {} total objects to {}",
-        ordered.len(),
+        written,
         args.output_file
     )?;
 
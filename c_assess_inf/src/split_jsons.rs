@@ -19,15 +19,16 @@ cargo jssplit \
     $(printf -- '-k %s ' "${KEEP[@]}")
 */
 
-use std::{collections::HashSet, fs, path::PathBuf};
-use anyhow::{Context, Result};
+use std::{collections::HashSet, path::PathBuf};
+use anyhow::{bail, Result};
+use c_assess_inf::stream::{self, RecordWriter};
 use clap::Parser;
 use serde_json::{Map, Value};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    // Input JSON file containing an array of objects
+    // Input file: JSON array or JSONL of objects
     #[arg(short, long)]
     input: PathBuf,
 
@@ -51,21 +52,17 @@ struct Args {
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Read input JSON
-    let raw = fs::read_to_string(&args.input)
-        .with_context(|| format!("Failed to read {}", args.input.display()))?;
-    let records: Vec<Value> = serde_json::from_str(&raw)
-        .with_context(|| "Input must be a JSON array of objects")?;
-
     // Pre‑compute sets for quick lookup
     let dup_set: HashSet<&str> = args.duplicate.iter().map(String::as_str).collect();
     let keep_set: HashSet<&str> = args.keep.iter().map(String::as_str).collect();
 
-    let mut out_a: Vec<Value> = Vec::with_capacity(records.len());
-    let mut out_b: Vec<Value> = Vec::with_capacity(records.len());
+    let mut out_a = RecordWriter::create(&args.output_a)?;
+    let mut out_b = RecordWriter::create(&args.output_b)?;
 
-    for rec in records {
-        let obj = rec.as_object().cloned().context("Expected object items")?;
+    for rec in stream::open(&args.input)? {
+        let Value::Object(obj) = rec? else {
+            bail!("Expected object items in {}", args.input.display());
+        };
 
         let mut a_map = Map::new();
         let mut b_map = Map::new();
@@ -83,19 +80,17 @@ fn main() -> Result<()> {
             }
         }
 
-        out_a.push(Value::Object(a_map));
-        out_b.push(Value::Object(b_map));
+        out_a.write(&Value::Object(a_map))?;
+        out_b.write(&Value::Object(b_map))?;
     }
 
-    // Write outputs
-    fs::write(&args.output_a, serde_json::to_string_pretty(&out_a)?)
-        .with_context(|| format!("Failed to write {}", args.output_a.display()))?;
-    fs::write(&args.output_b, serde_json::to_string_pretty(&out_b)?)
-        .with_context(|| format!("Failed to write {}", args.output_b.display()))?;
+    // Move the outputs into place
+    let written = out_a.finish()?;
+    out_b.finish()?;
 
     println!(
         "Wrote {} records to {} and {}",
-        out_a.len(),
+        written,
         args.output_a.display(),
         args.output_b.display()
     );
@@ -36,14 +36,11 @@ cargo countsplit \
     300
 */
 
-use std::{
-    env,
-    fs::File,
-    io::{BufReader, BufWriter},
-    process,
-};
+use std::{env, path::Path, process};
 
-use serde_json::{self, Value};
+use anyhow::Result;
+use c_assess_inf::stream::{self, RecordWriter};
+use serde_json::Value;
 
 fn main() {
     // CLI parsing
@@ -55,22 +52,21 @@ fn main() {
         );
         process::exit(1);
     }
-    let input_path = &args[1];
-    let out_low_path = &args[2];
-    let out_high_path = &args[3];
     let split_id: i64 = args[4].parse().expect("SPLIT_ID must be an integer");
 
-    // Read entire input JSON (expected to be an array)
-    let infile = File::open(input_path).expect("Cannot open input file");
-    let reader = BufReader::new(infile);
-    let mut data: Vec<Value> = serde_json::from_reader(reader)
-        .expect("Input must be a JSON array of objects");
+    if let Err(e) = split(&args[1], &args[2], &args[3], split_id) {
+        eprintln!("{e:#}");
+        process::exit(1);
+    }
+}
 
-    // Partition the records
-    let mut low: Vec<Value> = Vec::new();
-    let mut high: Vec<Value> = Vec::new();
+// Stream the input (JSON array or JSONL) into the two outputs
+fn split(input_path: &str, out_low_path: &str, out_high_path: &str, split_id: i64) -> Result<()> {
+    let mut low = RecordWriter::create(Path::new(out_low_path))?;
+    let mut high = RecordWriter::create(Path::new(out_high_path))?;
 
-    for v in data.drain(..) {
+    for v in stream::open(Path::new(input_path))? {
+        let v = v?;
         // Safely grab prompt_count; treat missing / non-int as 0
         let pc = v
             .get("prompt_count")
@@ -79,25 +75,13 @@ fn main() {
 
         if pc <= split_id {
             // to pc < split_id for a strict split
-            low.push(v);
+            low.write(&v)?;
         } else {
-            high.push(v);
+            high.write(&v)?;
         }
     }
 
-    // Write the two outputs
-    write_json(out_low_path, &low);
-    write_json(out_high_path, &high);
-}
-
-fn write_json(path: &str, payload: &[Value]) {
-    let outfile = File::create(path).unwrap_or_else(|e| {
-        eprintln!("Cannot create {}: {}", path, e);
-        process::exit(1);
-    });
-    let writer = BufWriter::new(outfile);
-    serde_json::to_writer_pretty(writer, payload).unwrap_or_else(|e| {
-        eprintln!("Failed to write {}: {}", path, e);
-        process::exit(1);
-    });
+    low.finish()?;
+    high.finish()?;
+    Ok(())
 }
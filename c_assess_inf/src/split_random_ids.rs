@@ -4,9 +4,9 @@ cargo split_random_ids \
     150 150 200
 */
 
-use std::{env, fs};
+use std::env;
 use std::path::Path;
-use serde_json::Value;
+use c_assess_inf::stream::{self, RecordWriter};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line
@@ -22,10 +22,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
              .expect("Counts must be positive integers"))
         .collect();
 
-    // Read and deserialize the JSON array
-    let raw = fs::read_to_string(&input_path)?;
-    let records: Vec<Value> = serde_json::from_str(&raw)
-        .expect("Input must be a JSON array of objects");
+    // Stream the records (JSON array or JSONL); parts keep the input format
+    let records = stream::open(Path::new(&input_path))?;
+    let format = records.format();
 
     // Prepare some path helpers
     let input_path = Path::new(&input_path);
@@ -35,38 +34,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ext    = input_path.extension()
         .and_then(|s| s.to_str()).unwrap_or("json");
 
-    // Split & write
-    let mut start = 0;
-    for (idx, &count) in counts.iter().enumerate() {
-        if start >= records.len() { break; }
-
-        let end = (start + count).min(records.len());
-        write_part(&records[start..end],
-                   parent, stem, ext, idx + 1)?;
-        start = end;
+    // Split & write; a part is only created once it gets a record, and
+    // whatever is left after the counts goes to one more part
+    let mut part = 0;
+    let mut current: Option<RecordWriter> = None;
+    let mut room = 0;
+    for rec in records {
+        let rec = rec?;
+        // `while`: a part with count 0 is written empty
+        while room == 0 {
+            if let Some(done) = current.take() {
+                finish_part(done)?;
+            }
+            part += 1;
+            room = counts.get(part - 1).copied().unwrap_or(usize::MAX);
+            let path = parent.join(format!("{stem}_part{part}.{ext}"));
+            current = Some(RecordWriter::with_format(&path, format)?);
+        }
+        current.as_mut().expect("part is open").write(&rec)?;
+        room -= 1;
     }
-
-    // Leftovers
-    if start < records.len() {
-        write_part(&records[start..],
-                   parent, stem, ext,
-                   counts.len() + 1 /* suffix */)?;
-        println!("(Leftover {} object(s) → *_extra.json)", records.len() - start);
+    if let Some(done) = current {
+        let leftover = part > counts.len();
+        let written = finish_part(done)?;
+        if leftover {
+            println!("(Leftover {} object(s) → *_extra.json)", written);
+        }
     }
     Ok(())
 }
 
-fn write_part(slice: &[Value],
-              dir:   &Path,
-              stem:  &str,
-              ext:   &str,
-              part:  usize) -> Result<(), Box<dyn std::error::Error>> {
-
-    let extra = if part > 1 && slice.len() < 1 { "_extra" } else { "" };
-    let filename = format!("{stem}_part{part}{extra}.{ext}");
-    let path = dir.join(filename);
-
-    fs::write(&path, serde_json::to_string_pretty(slice)?)?;
-    println!("Wrote {} objects → {}", slice.len(), path.display());
-    Ok(())
+fn finish_part(part: RecordWriter) -> Result<usize, Box<dyn std::error::Error>> {
+    let path = part.path().to_path_buf();
+    let written = part.finish()?;
+    println!("Wrote {} objects → {}", written, path.display());
+    Ok(written)
 }
@@ -0,0 +1,399 @@
+// Record streams for the merge/split utilities.
+//
+// A file is either one JSON array of records or JSONL (one record per line);
+// the reader tells them apart by the first non-blank byte (`[` = array) and
+// hands out one record at a time, so a tool never holds more than the record
+// it is working on. Array elements are cut out of the byte stream by
+// bracket depth and parsed on their own.
+//
+// The writer picks the format from the output extension (`.jsonl`/`.ndjson`
+// = JSONL, anything else = pretty array, byte-identical to
+// `to_string_pretty` of the whole array). It writes to `<name>.partial` and
+// renames on `finish`, so `-i x.json -o x.json` works and a failed run never
+// leaves half a file behind.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Array,
+    Jsonl,
+}
+
+impl Format {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("jsonl" | "ndjson") => Self::Jsonl,
+            _ => Self::Array,
+        }
+    }
+}
+
+// `.json`, `.jsonl` or `.ndjson`
+pub fn is_record_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()), Some("json" | "jsonl" | "ndjson"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    // array: before the first element
+    Open,
+    // array: after an element, expecting `,` or `]`
+    Between,
+    Done,
+}
+
+pub struct Records {
+    reader: Box<dyn BufRead>,
+    source: String,
+    format: Format,
+    state: State,
+    // records handed out so far
+    count: usize,
+    line: String,
+}
+
+// Stream the records of `path`; `-` reads stdin
+pub fn open(path: &Path) -> Result<Records> {
+    if path.as_os_str() == "-" {
+        return Records::new(Box::new(BufReader::new(io::stdin())), "<stdin>");
+    }
+    let file = File::open(path).with_context(|| format!("cannot read {}", path.display()))?;
+    Records::new(Box::new(BufReader::with_capacity(1 << 16, file)), &path.display().to_string())
+}
+
+impl Records {
+    pub fn new(mut reader: Box<dyn BufRead>, source: &str) -> Result<Self> {
+        skip_bom(&mut reader).with_context(|| format!("cannot read {source}"))?;
+        let first = skip_ws(&mut reader).with_context(|| format!("cannot read {source}"))?;
+        let (format, state) = match first {
+            Some(b'[') => {
+                reader.consume(1);
+                (Format::Array, State::Open)
+            }
+            Some(_) => (Format::Jsonl, State::Between),
+            None => (Format::Jsonl, State::Done),
+        };
+        Ok(Self { reader, source: source.to_string(), format, state, count: 0, line: String::new() })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn next_line(&mut self) -> Result<Option<Value>> {
+        loop {
+            self.line.clear();
+            let n = self
+                .reader
+                .read_line(&mut self.line)
+                .with_context(|| format!("cannot read {}", self.source))?;
+            if n == 0 {
+                self.state = State::Done;
+                return Ok(None);
+            }
+            if self.line.trim().is_empty() {
+                continue;
+            }
+            let value = serde_json::from_str(&self.line)
+                .with_context(|| format!("{}: record {} is not valid JSON", self.source, self.count + 1))?;
+            return Ok(Some(value));
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<Value>> {
+        let ctx = || format!("cannot read {}", self.source);
+        let next = skip_ws(&mut self.reader).with_context(ctx)?;
+        match (self.state, next) {
+            (State::Open, Some(b']')) | (State::Between, Some(b']')) => {
+                self.reader.consume(1);
+                self.state = State::Done;
+                if skip_ws(&mut self.reader).with_context(ctx)?.is_some() {
+                    bail!("{}: trailing characters after the closing `]`", self.source);
+                }
+                return Ok(None);
+            }
+            (State::Between, Some(b',')) => {
+                self.reader.consume(1);
+                if skip_ws(&mut self.reader).with_context(ctx)? == Some(b']') {
+                    bail!("{}: trailing comma after record {}", self.source, self.count);
+                }
+            }
+            (State::Open, Some(_)) => {}
+            (_, Some(b)) => bail!(
+                "{}: expected `,` or `]` after record {}, found `{}`",
+                self.source,
+                self.count,
+                b as char
+            ),
+            (_, None) => bail!("{}: unexpected end of file inside the array", self.source),
+        }
+
+        let raw = cut_element(&mut self.reader).with_context(ctx)?;
+        let value = serde_json::from_slice(&raw)
+            .with_context(|| format!("{}: record {} is not valid JSON", self.source, self.count + 1))?;
+        self.state = State::Between;
+        Ok(Some(value))
+    }
+}
+
+impl Iterator for Records {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        let next = match self.format {
+            Format::Array => self.next_element(),
+            Format::Jsonl => self.next_line(),
+        };
+        match next {
+            Ok(Some(value)) => {
+                self.count += 1;
+                Some(Ok(value))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn skip_bom(reader: &mut Box<dyn BufRead>) -> io::Result<()> {
+    if reader.fill_buf()?.starts_with(b"\xEF\xBB\xBF") {
+        reader.consume(3);
+    }
+    Ok(())
+}
+
+// Skip whitespace and return the next byte without consuming it
+fn skip_ws(reader: &mut Box<dyn BufRead>) -> io::Result<Option<u8>> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => {
+                let b = buf[i];
+                reader.consume(i);
+                return Ok(Some(b));
+            }
+            None => {
+                let n = buf.len();
+                reader.consume(n);
+            }
+        }
+    }
+}
+
+// The bytes of one array element: up to the bracket that closes it, the
+// quote that ends it, or (for a bare number/literal) the next `,`, `]` or
+// blank, which is left in the reader
+fn cut_element(reader: &mut Box<dyn BufRead>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut in_str = false;
+    let mut escaped = false;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(out);
+        }
+        let mut used = 0;
+        let mut done = false;
+        for &b in buf {
+            if in_str {
+                used += 1;
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_str = false;
+                    done = depth == 0;
+                }
+            } else {
+                if depth == 0 && (used > 0 || !out.is_empty()) && (b == b',' || b == b']' || b.is_ascii_whitespace()) {
+                    done = true;
+                    break;
+                }
+                used += 1;
+                match b {
+                    b'"' => in_str = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth = depth.saturating_sub(1);
+                        done = depth == 0;
+                    }
+                    _ => {}
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        out.extend_from_slice(&buf[..used]);
+        reader.consume(used);
+        if done {
+            return Ok(out);
+        }
+    }
+}
+
+pub struct RecordWriter {
+    out: BufWriter<File>,
+    path: PathBuf,
+    partial: PathBuf,
+    format: Format,
+    count: usize,
+    finished: bool,
+}
+
+impl RecordWriter {
+    // Format from the extension of `path`
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::with_format(path, Format::for_path(path))
+    }
+
+    pub fn with_format(path: &Path, format: Format) -> Result<Self> {
+        let mut name = path.file_name().context("output path has no file name")?.to_os_string();
+        name.push(".partial");
+        let partial = path.with_file_name(name);
+        let file = File::create(&partial).with_context(|| format!("cannot create {}", partial.display()))?;
+        Ok(Self { out: BufWriter::new(file), path: path.to_path_buf(), partial, format, count: 0, finished: false })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write<T: Serialize + ?Sized>(&mut self, record: &T) -> Result<()> {
+        self.write_record(record).with_context(|| format!("cannot write {}", self.partial.display()))
+    }
+
+    fn write_record<T: Serialize + ?Sized>(&mut self, record: &T) -> Result<()> {
+        match self.format {
+            Format::Jsonl => {
+                serde_json::to_writer(&mut self.out, record)?;
+                self.out.write_all(b"\n")?;
+            }
+            Format::Array => {
+                self.out.write_all(if self.count == 0 { b"[\n  " } else { b",\n  " })?;
+                // strings never hold a raw newline, so every newline here
+                // starts a line of the record
+                let pretty = serde_json::to_vec_pretty(record)?;
+                for (i, line) in pretty.split(|&b| b == b'\n').enumerate() {
+                    if i > 0 {
+                        self.out.write_all(b"\n  ")?;
+                    }
+                    self.out.write_all(line)?;
+                }
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    // Close the array, flush and move the file into place; returns the
+    // number of records written
+    pub fn finish(mut self) -> Result<usize> {
+        let ctx = || format!("cannot write {}", self.partial.display());
+        if self.format == Format::Array {
+            self.out.write_all(if self.count == 0 { b"[]" } else { b"\n]" }).with_context(ctx)?;
+        }
+        self.out.flush().with_context(ctx)?;
+        fs::rename(&self.partial, &self.path)
+            .with_context(|| format!("cannot move {} to {}", self.partial.display(), self.path.display()))?;
+        self.finished = true;
+        Ok(self.count)
+    }
+}
+
+// A writer dropped before `finish` (an error midway) removes its partial file
+impl Drop for RecordWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = fs::remove_file(&self.partial);
+        }
+    }
+}
+
+// Records of one file picked by their position, served in ascending key
+// order. If the picks already come in that order the file is streamed;
+// otherwise the picked records are loaded, which is the most a join over
+// unsorted input can do.
+pub struct Picked {
+    source: PickedSource,
+}
+
+enum PickedSource {
+    Stream { records: Records, position: usize, picks: HashMap<usize, i64> },
+    Loaded(HashMap<i64, Value>),
+}
+
+impl Picked {
+    // `picks`: record position in the file -> key
+    pub fn open(path: &Path, picks: HashMap<usize, i64>) -> Result<Self> {
+        let mut order: Vec<(usize, i64)> = picks.iter().map(|(&pos, &key)| (pos, key)).collect();
+        order.sort_unstable();
+        let sorted = order.windows(2).all(|w| w[0].1 < w[1].1);
+
+        let records = open(path)?;
+        let source = if sorted {
+            PickedSource::Stream { records, position: 0, picks }
+        } else {
+            let mut loaded = HashMap::with_capacity(picks.len());
+            for (pos, rec) in records.enumerate() {
+                let rec = rec?;
+                if let Some(&key) = picks.get(&pos) {
+                    loaded.insert(key, rec);
+                }
+            }
+            PickedSource::Loaded(loaded)
+        };
+        Ok(Self { source })
+    }
+
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.source, PickedSource::Stream { .. })
+    }
+
+    // The picked record with `key`; keys must be asked for in ascending order
+    pub fn take(&mut self, key: i64) -> Result<Value> {
+        match &mut self.source {
+            PickedSource::Loaded(map) => map.remove(&key).with_context(|| format!("no picked record with key {key}")),
+            PickedSource::Stream { records, position, picks } => loop {
+                let rec = records
+                    .next()
+                    .with_context(|| format!("{} ended before the record with key {key}", records.source()))??;
+                let pos = *position;
+                *position += 1;
+                match picks.get(&pos) {
+                    Some(&k) if k == key => return Ok(rec),
+                    Some(&k) => bail!(
+                        "{} changed while reading: record {} has key {k}, expected {key}",
+                        records.source(),
+                        pos + 1
+                    ),
+                    None => continue,
+                }
+            },
+        }
+    }
+}
@@ -4,13 +4,19 @@ cargo summary \
   --release \
   -- \
   c_assess_inf/output/alpaca_newphras/gemma-2-2b-it/summary_obstruction
+
+Graded-scale keys (`instruct_<dim>_L1..LN`, see `[scales.*]` in
+a_data/paraphrase_taxonomy.toml) additionally get a per-dimension trend:
+the mean of every level, the least-squares slope per level and whether the
+means are monotonic, overall and per metric.
 */
 
 use anyhow::{bail, Context, Result};
+use c_assess_inf::stream;
 use clap::Parser;
-use serde_json::Value;
+use paraphrx_core::scale::parse_scale_key;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     path::{Path, PathBuf},
 };
@@ -124,7 +130,7 @@ fn main() -> Result<()> {
     {
         let entry = entry?;
         if !entry.file_type()?.is_file()
-            || entry.path().extension().and_then(|s| s.to_str()) != Some("json")
+            || !stream::is_record_file(&entry.path())
         {
             continue;
         }
@@ -136,6 +142,7 @@ fn main() -> Result<()> {
     }
 
     report(&by_paraphrase, &by_metric);
+    report_scales(&by_paraphrase);
     Ok(())
 }
 
@@ -144,11 +151,8 @@ fn process_file(
     by_paraphrase: &mut HashMap<String, ParaphraseAgg>,
     by_metric: &mut [MetricAgg; METRIC_COUNT],
 ) -> Result<()> {
-    let data = fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
-    let records: Vec<Value> =
-        serde_json::from_str(&data).with_context(|| format!("Parsing {}", path.display()))?;
-
-    for rec in records {
+    for rec in stream::open(path)? {
+        let rec = rec?;
         let obj = rec
             .as_object()
             .with_context(|| format!("Top-level JSON value must be object in {}", path.display()))?;
@@ -260,3 +264,75 @@ fn report(by_para: &HashMap<String, ParaphraseAgg>, by_metric: &[MetricAgg; METR
         );
     }
 }
+
+// Least-squares slope of the means over their level numbers
+fn slope(points: &[(f64, f64)]) -> f64 {
+    let n = points.len() as f64;
+    let mx = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mx) * (y - my)).sum();
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mx).powi(2)).sum();
+    if sxx == 0.0 { 0.0 } else { sxy / sxx }
+}
+
+fn trend(points: &[(f64, f64)]) -> &'static str {
+    const EPS: f64 = 1e-9;
+    let steps: Vec<f64> = points.windows(2).map(|w| w[1].1 - w[0].1).collect();
+    if steps.iter().all(|d| d.abs() < EPS) {
+        "flat"
+    } else if steps.iter().all(|d| *d > -EPS) {
+        "↑ monotonic"
+    } else if steps.iter().all(|d| *d < EPS) {
+        "↓ monotonic"
+    } else {
+        "non-monotonic"
+    }
+}
+
+fn report_scales(by_para: &HashMap<String, ParaphraseAgg>) {
+    let mut dims: BTreeMap<&str, Vec<(u32, &ParaphraseAgg)>> = BTreeMap::new();
+    for (key, stats) in by_para {
+        if let Some((dim, level)) = parse_scale_key(key) {
+            dims.entry(dim).or_default().push((level, stats));
+        }
+    }
+    dims.retain(|_, levels| levels.len() > 1);
+    if dims.is_empty() {
+        return;
+    }
+
+    println!("\n================== GRADED SCALES ==================");
+    for (dim, levels) in &mut dims {
+        levels.sort_by_key(|(level, _)| *level);
+        let overall: Vec<(f64, f64)> =
+            levels.iter().map(|(l, s)| (*l as f64, s.overall_avg())).collect();
+        println!(
+            "► {dim} (L{}..L{}, {} levels)",
+            levels[0].0,
+            levels[levels.len() - 1].0,
+            levels.len()
+        );
+        let means = |points: &[(f64, f64)]| {
+            points.iter().map(|(_, y)| format!("{y:5.2}")).collect::<Vec<_>>().join(" ")
+        };
+        println!(
+            "    {:38}  {}  | slope {:+.3}/level  {}",
+            "overall",
+            means(&overall),
+            slope(&overall),
+            trend(&overall)
+        );
+        for (i, name) in METRIC_NAMES.iter().enumerate() {
+            let points: Vec<(f64, f64)> = levels.iter().map(|(l, s)| (*l as f64, s.avg(i))).collect();
+            println!(
+                "    {:2}. {:34}  {}  | slope {:+.3}/level  {}",
+                i + 1,
+                name,
+                means(&points),
+                slope(&points),
+                trend(&points)
+            );
+        }
+        println!();
+    }
+}
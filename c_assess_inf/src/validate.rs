@@ -0,0 +1,68 @@
+/*
+cargo validate scores \
+    c_assess_inf/output/alpaca/gemma-2-9b-it/scores_500/style.json \
+    c_assess_inf/output/alpaca/gemma-2-9b-it/scores_500/obstruction.json
+
+cargo validate paraphrases a_data/alpaca/prxed/all.json
+cargo validate issues c_assess_inf/output/alpaca/gemma-2-9b-it/scores_issues_500/style_issues.json
+
+# print the bundled schema
+cargo validate equivalence --print-schema
+*/
+
+use anyhow::Result;
+use c_assess_inf::contracts::{validate_file, Kind};
+use clap::Parser;
+use std::{path::PathBuf, process};
+
+// Check pipeline artifacts against their versioned contracts and list every
+// violation with its JSON pointer; exits with 1 if any file fails
+#[derive(Parser, Debug)]
+#[command(version, author, about)]
+struct Cli {
+    #[arg(value_enum)]
+    kind: Kind,
+
+    #[arg(required_unless_present = "print_schema")]
+    files: Vec<PathBuf>,
+
+    // Print at most N violations per file (0: all)
+    #[arg(long, default_value_t = 0)]
+    max: usize,
+
+    // Print the schema of <KIND> and exit
+    #[arg(long)]
+    print_schema: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.print_schema {
+        print!("{}", cli.kind.schema_text());
+        return Ok(());
+    }
+
+    let schema = cli.kind.schema_id();
+    let mut failed = 0usize;
+    for path in &cli.files {
+        let violations = validate_file(cli.kind, path)?;
+        if violations.is_empty() {
+            println!("ok    {} ({schema})", path.display());
+            continue;
+        }
+        failed += 1;
+        println!("FAIL  {} ({schema}): {} violation(s)", path.display(), violations.len());
+        let shown = if cli.max == 0 { violations.len() } else { cli.max };
+        for v in violations.iter().take(shown) {
+            println!("      {v}");
+        }
+        if violations.len() > shown {
+            println!("      … {} more", violations.len() - shown);
+        }
+    }
+    println!("{} of {} file(s) valid", cli.files.len() - failed, cli.files.len());
+    if failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+// The record streams behind the merge/split utilities: arrays cut out by
+// bracket depth, JSONL, and the writer's byte-identical pretty arrays.
+//
+//   cargo test -p c_assess_inf --test stream
+
+use c_assess_inf::stream::{self, Format, RecordWriter, Records};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{BufReader, Cursor},
+    path::PathBuf,
+};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("c_assess_inf_stream_{}_{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Every record of `input`, read through a buffer of `capacity` bytes
+fn read_with(input: &str, capacity: usize) -> (Format, Result<Vec<Value>, String>) {
+    let reader = BufReader::with_capacity(capacity, Cursor::new(input.as_bytes().to_vec()));
+    let records = Records::new(Box::new(reader), "test").unwrap();
+    let format = records.format();
+    (format, records.collect::<anyhow::Result<Vec<_>>>().map_err(|e| format!("{e:#}")))
+}
+
+fn read(input: &str) -> Vec<Value> {
+    read_with(input, 1 << 16).1.unwrap()
+}
+
+fn read_err(input: &str) -> String {
+    read_with(input, 1 << 16).1.unwrap_err()
+}
+
+#[test]
+fn escaped_quotes_and_brackets_inside_strings() {
+    let records = vec![
+        json!({"instruction": "say \"]\" then \"[\"", "output": "a \\ b"}),
+        json!({"output": "}{ ]] \\\" ,"}),
+        json!(["[", "]", "\\"]),
+        json!("a \"quoted\" ] string"),
+    ];
+    let input = serde_json::to_string(&records).unwrap();
+    // every buffer size, so each escape also lands on a buffer boundary
+    for capacity in 1..=16 {
+        assert_eq!(read_with(&input, capacity).1.unwrap(), records, "capacity {capacity}");
+    }
+}
+
+#[test]
+fn elements_cross_the_64_kib_buffer() {
+    let records: Vec<Value> =
+        (0..6).map(|i| json!({"prompt_count": i, "output": format!("{i} \"{}\"", "]x".repeat(20_000))})).collect();
+    let dir = scratch("boundary");
+    let path = dir.join("big.json");
+    fs::write(&path, serde_json::to_string_pretty(&records).unwrap()).unwrap();
+    assert!(fs::metadata(&path).unwrap().len() > 3 * (1 << 16));
+
+    let read: Vec<Value> = stream::open(&path).unwrap().collect::<anyhow::Result<_>>().unwrap();
+    assert_eq!(read, records);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bare_numbers_and_literals_are_elements() {
+    let expected = vec![json!(1), json!(-2.5e3), json!(true), json!(false), json!(null), json!("s"), json!(0)];
+    assert_eq!(read("[1,-2.5e3,true,false,null,\"s\",0]"), expected);
+    assert_eq!(read("[ 1 ,\n -2.5e3\t, true , false,null ,\"s\" , 0 ]\n"), expected);
+    for capacity in 1..=4 {
+        assert_eq!(read_with("[12345,true,null]", capacity).1.unwrap(), [json!(12345), json!(true), json!(null)]);
+    }
+}
+
+#[test]
+fn empty_arrays_trailing_commas_and_garbage() {
+    assert_eq!(read_with("[]", 1 << 16), (Format::Array, Ok(Vec::new())));
+    assert_eq!(read(" [ \n ] \n"), Vec::<Value>::new());
+    assert_eq!(read_with("", 1 << 16), (Format::Jsonl, Ok(Vec::new())));
+    assert_eq!(read_with(" \n\n", 1 << 16), (Format::Jsonl, Ok(Vec::new())));
+
+    assert!(read_err("[1, 2,]").contains("trailing comma after record 2"));
+    assert!(read_err("[{\"a\": 1},\n]").contains("trailing comma after record 1"));
+    assert!(read_err("[1, 2] x").contains("trailing characters after the closing `]`"));
+    assert!(read_err("[1, 2]]").contains("trailing characters"));
+    assert!(read_err("[1 2]").contains("expected `,` or `]` after record 1, found `2`"));
+    assert!(read_err("[1, 2").contains("unexpected end of file inside the array"));
+    assert!(read_err("[{\"a\": 1}, {\"a\": }]").contains("record 2 is not valid JSON"));
+}
+
+#[test]
+fn bom_at_the_start() {
+    let (format, records) = read_with("\u{feff}[{\"a\": 1}, {\"a\": 2}]", 1 << 16);
+    assert_eq!(format, Format::Array);
+    assert_eq!(records.unwrap(), [json!({"a": 1}), json!({"a": 2})]);
+
+    let (format, records) = read_with("\u{feff}{\"a\": 1}\n\n{\"a\": 2}\n", 1 << 16);
+    assert_eq!(format, Format::Jsonl);
+    assert_eq!(records.unwrap(), [json!({"a": 1}), json!({"a": 2})]);
+}
+
+#[test]
+fn record_writer_matches_to_string_pretty() {
+    let dir = scratch("writer");
+    let cases = [
+        Vec::new(),
+        vec![json!({})],
+        vec![json!({"prompt_count": 1, "instruction": "line one\nline \"two\"", "scores": [1, 2, 3], "nested": {"a": [], "b": {}}})],
+        vec![json!(1), json!("s"), json!(null), json!([[1, 2], []]), json!({"k": [{"x": true}]})],
+    ];
+    for (i, records) in cases.iter().enumerate() {
+        let path = dir.join(format!("out{i}.json"));
+        let mut writer = RecordWriter::create(&path).unwrap();
+        for rec in records {
+            writer.write(rec).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), records.len());
+        assert_eq!(fs::read_to_string(&path).unwrap(), serde_json::to_string_pretty(records).unwrap(), "case {i}");
+        assert!(!dir.join(format!("out{i}.json.partial")).exists());
+
+        let read: Vec<Value> = stream::open(&path).unwrap().collect::<anyhow::Result<_>>().unwrap();
+        assert_eq!(&read, records);
+    }
+
+    // JSONL: one compact record per line
+    let path = dir.join("out.jsonl");
+    let mut writer = RecordWriter::create(&path).unwrap();
+    writer.write(&json!({"a": 1})).unwrap();
+    writer.write(&json!([2])).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\":1}\n[2]\n");
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,4 @@
+// Shared pieces of the finetuning binaries; the judge core lives in
+// paraphrx_core and is re-exported under the old paths
+
+pub use paraphrx_core::{cache, contracts, dryrun, scrub, usage};
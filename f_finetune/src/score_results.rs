@@ -8,21 +8,24 @@ Use: cargo run --release --bin results_assess_api_limited -- \
 */
 
 use anyhow::{anyhow, Context, Result};
+use f_finetune::cache::{self, CacheMode, DEFAULT_CACHE_DIR};
+use f_finetune::contracts::{self, Kind};
+use f_finetune::dryrun;
+use f_finetune::scrub::{self, DEFAULT_SCRUB_PATTERNS};
+use f_finetune::usage::{self, guess_dataset, guess_version_set, DEFAULT_USAGE_DIR};
 use chrono::Local;
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 use once_cell::sync::Lazy;
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
-use serde::{Deserialize, Serialize};
+use paraphrx_core::{build_client, build_finetune_eval_prompt, query_gemini, read_records, Logger, Query, Record};
 use serde_json::{json, Map as JsonMap, Value};
 use std::{
     collections::{HashMap, HashSet},
-    fs, io::BufWriter,
+    fs,
     path::{Path, PathBuf},
     time::Duration,
 };
 use tokio::time::sleep;
-use std::io::Write;
 
 const PROMPT_PREAMBLE_TOKENS: usize = 500;
 const DEBUG_IDS: &[u32] = &[1, 42, 311];
@@ -38,72 +41,12 @@ static MODEL_LIMITS: Lazy<HashMap<&'static str, usize>> = Lazy::new(|| {
     ])
 });
 
-const ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
-
 // Misc helpers
 fn estimate_tokens(text: &str) -> usize {
     // very rough: 0.75 * words ≈ tokens   (≈ bytes / 4)
     ((text.split_whitespace().count() as f32) * 0.75).ceil() as usize
 }
 
-// Logger
-struct Logger {
-    writer: BufWriter<fs::File>,
-}
-impl Logger {
-    fn new<P: AsRef<Path>>(p: P) -> Result<Self> {
-        let file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(p)?;
-        Ok(Self {
-            writer: BufWriter::new(file),
-        })
-    }
-    fn log(&mut self, msg: &str) {
-        let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
-        let _ = writeln!(self.writer, "[{ts}] {msg}");
-        let _ = self.writer.flush();
-    }
-}
-
-// Data model
-fn de_prompt_count<'de, D>(de: D) -> std::result::Result<u32, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    struct Visitor;
-    impl<'de> serde::de::Visitor<'de> for Visitor {
-        type Value = u32;
-        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            f.write_str("integer or string for prompt_count")
-        }
-        fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
-            Ok(v as u32)
-        }
-        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            v.parse::<u32>()
-                .map_err(|_| E::custom(format!("invalid prompt_count {v}")))
-        }
-    }
-    de.deserialize_any(Visitor)
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct Record {
-    #[serde(alias = "prompt_count", deserialize_with = "de_prompt_count")]
-    prompt_count: u32,
-
-    #[serde(alias = "instruction", alias = "instruction_original", default)]
-    instruction_original: String,
-
-    #[serde(default)]
-    output: Option<String>,
-
-    #[serde(flatten)]
-    extra: JsonMap<String, Value>,
-}
-
 // CLI
 #[derive(Parser, Debug)]
 #[command(version, about = "Assess paraphrase answers with Gemini (resume-able, token-aware)")]
@@ -137,43 +80,46 @@ struct Cli {
     /// emergency upper bound on instruct_* per chunk
     #[arg(long = "chunk-max", default_value_t = 200)]
     chunk_max: usize,
-}
 
-// JSON helpers
-fn read_records(path: &Path, logger: &mut Logger) -> HashMap<String, Record> {
-    match fs::read_to_string(path)
-        .and_then(|s| serde_json::from_str::<Vec<Value>>(&s).map_err(Into::into))
-    {
-        Ok(items) => items
-            .into_iter()
-            .map(|raw| {
-                // 1. turn the raw JSON into our Record
-                let mut rec: Record = serde_json::from_value(raw.clone())?;
-
-                // 2. pull paraphrases into `extra`
-                if let Some(pars) = raw.get("paraphrases").and_then(Value::as_array) {
-                    for p in pars {
-                        if let (Some(t), Some(txt)) = (
-                            p.get("instruct_type").and_then(Value::as_str),
-                            p.get("paraphrase").and_then(Value::as_str),
-                        ) {
-                            rec.extra
-                                .insert(t.to_string(), Value::String(txt.to_string()));
-                        }
-                    }
-                }
-                Ok((rec.prompt_count.to_string(), rec))
-            })
-            .collect::<Result<_, anyhow::Error>>() // propagate errors
-            .unwrap_or_else(|e| {
-                logger.log(&format!("[fatal] {e}"));
-                HashMap::new()
-            }),
-        Err(e) => {
-            logger.log(&format!("[fatal-but-skipped] cannot parse {}: {e}", path.display()));
-            HashMap::new()
-        }
-    }
+    /// dataset / set recorded in the usage ledger (default: guessed from the answers path)
+    #[arg(long)]
+    dataset: Option<String>,
+
+    #[arg(long)]
+    version_set: Option<String>,
+
+    /// directory of the per-run token usage ledgers (see `cargo cost_report`)
+    #[arg(long, default_value = DEFAULT_USAGE_DIR)]
+    usage_dir: PathBuf,
+
+    /// response cache: read (replay stored answers, store new ones) | write (refresh) | off
+    #[arg(long, value_enum, default_value_t = CacheMode::Read)]
+    cache: CacheMode,
+
+    #[arg(long, default_value = DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
+
+    /// write every scoring prompt to `<DIR>/<id>_<chunk>.txt` and print size statistics
+    /// instead of calling Gemini; the output file is not written
+    #[arg(long, value_name = "DIR")]
+    dry_run: Option<PathBuf>,
+
+    /// check the instructions and answers against their contracts (see `cargo validate`)
+    /// and stop at the first file that breaks one
+    #[arg(long)]
+    validate_inputs: bool,
+
+    /// mask PII and secrets (a_data/scrub_patterns.toml) in every scoring prompt before
+    /// it is sent; also applies to --dry-run
+    #[arg(long)]
+    scrub: bool,
+
+    #[arg(long, default_value = DEFAULT_SCRUB_PATTERNS)]
+    scrub_patterns: PathBuf,
+
+    /// placeholder -> original map and redaction counts (default `<output>.scrub.json`)
+    #[arg(long, value_name = "PATH")]
+    scrub_map: Option<PathBuf>,
 }
 
 // Main
@@ -186,13 +132,43 @@ async fn main() -> Result<()> {
     let ts = Local::now().format("%Y%m%d-%H%M%S");
     let log_path = Path::new("logs")
         .join(format!("{}_{}_{}.log", cli.log_name, cli.output.file_stem().unwrap().to_string_lossy(), ts));
-    let mut logger = Logger::new(&log_path)?;
+    let mut logger = Logger::append(&log_path)?;
     logger.log(&format!(
         "run started – model={} margin={} api_cap={} ",
         cli.model, cli.margin, cli.api_call_max
     ));
+    if let Some(dir) = &cli.dry_run {
+        dryrun::init(dir)?;
+    } else {
+        let ledger = usage::init(
+            &cli.usage_dir,
+            "score_results",
+            &cli.output.file_stem().unwrap().to_string_lossy(),
+            cli.dataset.clone().or_else(|| guess_dataset(&cli.answers)).as_deref(),
+            cli.version_set.clone().or_else(|| guess_version_set(&cli.answers)).as_deref(),
+        )?;
+        logger.log(&format!("usage ledger {}", ledger.display()));
+    }
+    if cli.cache != CacheMode::Off {
+        cache::init(&cli.cache_dir, cli.cache)?;
+    }
+    if cli.scrub {
+        let map = cli.scrub_map.clone().unwrap_or_else(|| scrub::map_path_for(&cli.output));
+        let set = cli
+            .version_set
+            .clone()
+            .or_else(|| guess_version_set(&cli.answers))
+            .unwrap_or_else(|| cli.output.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+        scrub::init(&cli.scrub_patterns, &map, &set)?;
+        logger.log(&format!("scrubbing prompts with {}", cli.scrub_patterns.display()));
+    }
 
     // I/O
+    if cli.validate_inputs {
+        contracts::check_input(Kind::Paraphrases, &cli.instructions)?;
+        contracts::check_input(Kind::Answers, &cli.answers)?;
+        logger.log("inputs match their contracts");
+    }
     let instr_map = read_records(&cli.instructions, &mut logger);
     let ans_map = read_records(&cli.answers, &mut logger);
     if instr_map.is_empty() || ans_map.is_empty() {
@@ -222,8 +198,10 @@ async fn main() -> Result<()> {
         .api_key
         .clone()
         .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
+        // a dry run sends nothing
+        .or_else(|| dryrun::global().map(|_| String::new()))
         .context("provide --api-key or set GOOGLE_API_KEY")?;
-    let client = build_client()?;
+    let client = build_client(None)?;
 
     // preparation
     let mut instr_sorted: Vec<(&String, &Record)> = instr_map.iter().collect();
@@ -291,6 +269,7 @@ async fn main() -> Result<()> {
 
         // chunking
         let mut cursor = 0usize;
+        let mut chunk_no = 0;
         while cursor < pending.len() {
             if api_calls_used >= cli.api_call_max {
                 logger.log("API cap reached → aborting early");
@@ -320,8 +299,7 @@ async fn main() -> Result<()> {
                 // fall back to the legacy location inside `extra` for the original
                 let ans_text_raw = if key == "instruction_original" {
                     ans_map[id]
-                        .output
-                        .as_deref()
+                        .output_text()
                         .or_else(|| {
                             ans_map[id]
                                 .extra
@@ -386,7 +364,12 @@ async fn main() -> Result<()> {
                 cli.margin
             ));
 
-            let prompt = build_eval_prompt(&section);
+            let prompt = scrub::apply(build_finetune_eval_prompt(&section));
+            chunk_no += 1;
+            if let Some(d) = dryrun::global() {
+                d.write(&format!("{id}_{chunk_no}"), &prompt, &Value::Null)?;
+                continue;
+            }
             if DEBUG_IDS.contains(&inst.prompt_count) {
                 let dump = format!("logs/debug_prompt_{}_chunk{}.txt", id, chunk.len());
                 fs::write(&dump, &prompt)?;
@@ -398,7 +381,7 @@ async fn main() -> Result<()> {
             for attempt in 1..=cli.max_attempts {
                 attempt_used = attempt;
                 logger.log(&format!("API call (attempt {attempt}/{})", cli.max_attempts));
-                match query_gemini(&client, &api_key, &cli.model, &prompt).await {
+                match query_gemini(&client, &api_key, &cli.model, &prompt, &Query::default()).await {
                     Ok(obj) => {
                         success = true;
                         // merge result
@@ -436,6 +419,16 @@ async fn main() -> Result<()> {
     }
     bar.finish();
 
+    if let Some(d) = dryrun::global() {
+        logger.log(&format!("dry run: {}", d.summary()));
+        println!("dry run: {}", d.summary());
+        for line in scrub::finish()? {
+            logger.log(&format!("scrub: {line}"));
+            println!("scrub: {line}");
+        }
+        return Ok(());
+    }
+
     // save
     let mut vec_out: Vec<JsonMap<String, Value>> = scored
         .into_iter()
@@ -444,75 +437,14 @@ async fn main() -> Result<()> {
     vec_out.sort_by_key(|m| m.get("prompt_count").and_then(Value::as_u64).unwrap_or(0));
     fs::write(&cli.output, serde_json::to_string_pretty(&vec_out)?)?;
     logger.log("results written");
+    if let Some(c) = cache::global() {
+        logger.log(&format!("response cache: {}", c.stats()));
+    }
+    for line in scrub::finish()? {
+        logger.log(&format!("scrub: {line}"));
+        println!("scrub: {line}");
+    }
 
     println!("finished – log at {}", log_path.display());
     Ok(())
 }
-
-// HTTP & prompt
-fn build_client() -> Result<reqwest::Client> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    Ok(reqwest::Client::builder().default_headers(headers).build()?)
-}
-
-fn build_eval_prompt(section: &str) -> String {
-    format!(r#"You are an expert evaluator.
-
-For every answer below, assess it against **ten metrics**. Each metric must be scored on a 0-10 integer scale (higher is better).
-
-Metrics (use **exact** order):
-1. Task Fulfilment / Relevance - Does it respond to every part of the prompt? Did it wander off-topic or over-answer?
-2. Usefulness & Actionability - Does it translate abstract ideas into concrete advice, examples, or next steps?
-3. Factual Accuracy & Verifiabiliy - Are the statements factually correct (no hallucinations)? If it cites sources or internal steps, do those match the final claims?
-4. Efficiency / Depth & Completeness - Does it avoid unnecessary verbosity or excessive brevity? Does it cover the key angles, edge-cases, and typical follow-ups? Could the user act on it without having to ask “what about X?”
-5. Reasoning Quality / Transparency - Are the steps implicitly or explicitly sound? If uncertain, does it flag that uncertainty instead of bluffing?
-6. Tone & Likeability - Is the style friendly and respectful, matching the user’s vibe? Would you enjoy a longer conversation in this voice?
-7. Adaptation to Context - Does it use any relevant info the user has shared (location, preferences, prior messages) appropriately?
-8. Safety & Bias Avoidance - Does it steer clear of harmful or disallowed content? Does it acknowledge and mitigate possible bias?
-9. Structure & Formatting & UX Extras - Is the writing logically ordered and easy to skim? Are lists, code blocks, tables, or rich widgets used when, but only when they genuinely improve readability or utility?
-10. Creativity - Does the answer make clever, non-obvious connections that you wouldn’t get from a quick Google search? Or, does it remix ideas, metaphors, or examples in a fresh way rather than serving boilerplate?
-
-Return *only* valid JSON (no markdown, no code fences) where each key maps to an array of ten integers 0-10.
-The returned JSON must confirm to this Pydantic model:
-
-```python
-from typing import Dict, List
-from pydantic import BaseModel, conlist
-
-class EvalResult(BaseModel):
-    __root__: Dict[str, conlist(int, min_items=10, max_items=10)]
-```
-
-Begin data to evaluate:
-
-{section}
-"#)
-}
-
-async fn query_gemini(
-    client: &reqwest::Client,
-    key: &str,
-    model: &str,
-    prompt: &str,
-) -> Result<JsonMap<String, Value>> {
-    let url = format!("{ENDPOINT}/models/{model}:generateContent?key={key}");
-    let body = json!({
-        "contents": [{
-            "role": "user",
-            "parts": [{ "text": prompt }]
-        }],
-        "generationConfig": {
-            "responseMimeType": "application/json"
-        }
-    });
-    let resp = client.post(&url).json(&body).send().await?;
-    if !resp.status().is_success() {
-        return Err(anyhow!("{} — {}", resp.status(), resp.text().await?));
-    }
-    let resp_json: Value = resp.json().await?;
-    let json_text = resp_json["candidates"][0]["content"]["parts"][0]["text"]
-        .as_str()
-        .ok_or_else(|| anyhow!("unexpected response structure"))?;
-    Ok(serde_json::from_str(json_text.trim())?)
-}
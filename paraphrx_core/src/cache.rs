@@ -0,0 +1,158 @@
+// Content-addressed on-disk cache of LLM responses.
+//
+// A request is identified by the SHA-256 of (endpoint, model, request body);
+// the body carries the generation config, schema and prompt, the API key is
+// never part of it. The raw response JSON is stored under
+// `<dir>/<first two hex digits>/<hash>.json`, so re-running a slice after a
+// crash or an unrelated code change replays the answers instead of paying
+// for them again. Hits make no API call and are not written to the usage
+// ledger. Like the ledger the cache is process-wide: `init` it in `main`,
+// then `global()` is what each `query_gemini` consults.
+
+use crate::logger;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+};
+
+pub const DEFAULT_CACHE_DIR: &str = "cache";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CacheMode {
+    // serve stored responses, store new ones
+    Read,
+    // always call the API and overwrite what is stored (refresh)
+    Write,
+    // neither look up nor store
+    Off,
+}
+
+// Numbers the temp files of `put`
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    mode: CacheMode,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stored: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stored: u64,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hits, {} misses, {} stored", self.hits, self.misses, self.stored)
+    }
+}
+
+impl Cache {
+    pub fn new(dir: &Path, mode: CacheMode) -> Result<Self> {
+        if mode != CacheMode::Off {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create cache dir {}", dir.display()))?;
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            mode,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stored: AtomicU64::new(0),
+        })
+    }
+
+    pub fn key(endpoint: &str, model: &str, body: &Value) -> String {
+        let canonical = json!({ "endpoint": endpoint, "model": model, "body": body }).to_string();
+        Sha256::digest(canonical.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{key}.json"))
+    }
+
+    // Stored response for `key`; `None` unless the mode is `read`
+    pub fn get(&self, key: &str) -> Option<Value> {
+        if self.mode != CacheMode::Read {
+            return None;
+        }
+        let hit = fs::read_to_string(self.path(key))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok());
+        let counter = if hit.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    // Whether `get` would answer `key` from disk; does not count as a hit
+    pub fn contains(&self, key: &str) -> bool {
+        self.mode == CacheMode::Read && self.path(key).is_file()
+    }
+
+    // Store a response the caller could parse; failures only cost a warning
+    pub fn put(&self, key: &str, response: &Value) {
+        if self.mode == CacheMode::Off {
+            return;
+        }
+        let path = self.path(key);
+        // write-then-rename: concurrent readers never see half a file. The
+        // temp name is unique per write, since tasks of one process can store
+        // the same key at the same time
+        let seq = TMP_SEQ.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_extension(format!("tmp{}_{seq}", std::process::id()));
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp, response.to_string()))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        match written {
+            Ok(()) => {
+                self.stored.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => logger::warn(&format!("cache: failed to store {}: {e}", path.display())),
+        }
+    }
+
+    // Drop a stored response the caller rejected, so a retry asks the model again
+    pub fn forget(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stored: self.stored.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+pub fn init(dir: &Path, mode: CacheMode) -> Result<()> {
+    let _ = CACHE.set(Cache::new(dir, mode)?);
+    Ok(())
+}
+
+pub fn global() -> Option<&'static Cache> {
+    CACHE.get()
+}
@@ -0,0 +1,165 @@
+// File contracts of the pipeline artifacts.
+//
+// Every artifact kind has a versioned JSON Schema in `c_assess_inf/schemas/`
+// (compiled into the binaries) for the shape of one file; what a schema
+// cannot say, such as a `prompt_count` that appears twice, is checked here.
+// Violations carry the JSON pointer of the offending value, so
+// `/12/instruct_polite/3` is the fourth metric of that key in the 13th record.
+
+use anyhow::{bail, Context, Result};
+use jsonschema::{Draft, JSONSchema};
+use serde_json::Value;
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Kind {
+    // generator / merge_instructs output: `instruct_*` -> paraphrase text
+    Paraphrases,
+    // inference output: `instruct_*` -> model answer text
+    Answers,
+    // judge output: `instruct_*` -> 10 metric scores 0–10
+    Scores,
+    // phrx_equivalence_score output: `{prompt_count, scores: {key: 0–5}}`
+    Equivalence,
+    // `*_issues.json`: `id <n>: <reason>` lines
+    Issues,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Paraphrases => "paraphrases",
+            Self::Answers => "answers",
+            Self::Scores => "scores",
+            Self::Equivalence => "equivalence",
+            Self::Issues => "issues",
+        }
+    }
+
+    pub fn schema_text(self) -> &'static str {
+        match self {
+            Self::Paraphrases => include_str!("../../c_assess_inf/schemas/paraphrases.v1.schema.json"),
+            Self::Answers => include_str!("../../c_assess_inf/schemas/answers.v1.schema.json"),
+            Self::Scores => include_str!("../../c_assess_inf/schemas/scores.v1.schema.json"),
+            Self::Equivalence => include_str!("../../c_assess_inf/schemas/equivalence.v1.schema.json"),
+            Self::Issues => include_str!("../../c_assess_inf/schemas/issues.v1.schema.json"),
+        }
+    }
+
+    pub fn schema(self) -> Value {
+        serde_json::from_str(self.schema_text()).expect("bundled schema is valid JSON")
+    }
+
+    // `$id` of the bundled schema, e.g. `urn:paraphrx:scores:v1`
+    pub fn schema_id(self) -> String {
+        self.schema()["$id"].as_str().unwrap_or_default().to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Violation {
+    // JSON pointer into the file; empty for the whole document
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() { "/" } else { &self.pointer };
+        write!(f, "{pointer}: {}", self.message)
+    }
+}
+
+// Every violation of `doc` against the contract of `kind`
+pub fn validate(kind: Kind, doc: &Value) -> Vec<Violation> {
+    let schema = kind.schema();
+    let compiled = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .expect("bundled schema compiles");
+
+    let mut out: Vec<Violation> = match compiled.validate(doc) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|e| Violation { pointer: e.instance_path.to_string(), message: shorten(&e.to_string()) })
+            .collect(),
+    };
+    if kind != Kind::Issues {
+        out.extend(duplicate_ids(doc));
+    }
+    out.sort_by_cached_key(|v| pointer_key(&v.pointer));
+    out
+}
+
+// `/10/x` after `/9/x`: numeric segments compare as numbers
+fn pointer_key(pointer: &str) -> Vec<(u64, String)> {
+    pointer
+        .split('/')
+        .map(|seg| match seg.parse::<u64>() {
+            Ok(n) => (n, String::new()),
+            Err(_) => (u64::MAX, seg.to_string()),
+        })
+        .collect()
+}
+
+// Read and validate one file; a file that is not JSON is one violation
+pub fn validate_file(kind: Kind, path: &Path) -> Result<Vec<Violation>> {
+    let raw = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    match serde_json::from_str::<Value>(&raw) {
+        Ok(doc) => Ok(validate(kind, &doc)),
+        Err(e) => Ok(vec![Violation { pointer: String::new(), message: format!("not valid JSON: {e}") }]),
+    }
+}
+
+// Startup check for the `--validate-inputs` flag of the judge binaries:
+// fails with the first violations instead of letting a bad file be skipped
+pub fn check_input(kind: Kind, path: &Path) -> Result<()> {
+    let violations = validate_file(kind, path)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let shown: Vec<String> = violations.iter().take(20).map(|v| format!("  {v}")).collect();
+    bail!(
+        "{} is not a valid {} file ({}), {} violation(s):\n{}{}",
+        path.display(),
+        kind.name(),
+        kind.schema_id(),
+        violations.len(),
+        shown.join("\n"),
+        if violations.len() > shown.len() { "\n  …" } else { "" }
+    )
+}
+
+// `prompt_count` / `prompt_id` values that occur in more than one record
+fn duplicate_ids(doc: &Value) -> Vec<Violation> {
+    let Some(records) = doc.as_array() else { return Vec::new() };
+    let mut out = Vec::new();
+    for field in ["prompt_count", "prompt_id"] {
+        let mut first: HashMap<String, usize> = HashMap::new();
+        for (i, rec) in records.iter().enumerate() {
+            let Some(id) = rec.get(field).filter(|v| !v.is_null()) else { continue };
+            let id = id.to_string();
+            if let Some(j) = first.get(&id) {
+                out.push(Violation {
+                    pointer: format!("/{i}/{field}"),
+                    message: format!("duplicate {field} {id} (first at /{j})"),
+                });
+            } else {
+                first.insert(id, i);
+            }
+        }
+    }
+    out
+}
+
+// Schema messages start with the offending value, which can be a whole
+// record; keep its start and the reason at the end
+fn shorten(message: &str) -> String {
+    let chars: Vec<char> = message.chars().collect();
+    if chars.len() <= 200 {
+        return message.to_string();
+    }
+    let head: String = chars[..80].iter().collect();
+    let tail: String = chars[chars.len() - 100..].iter().collect();
+    format!("{head} … {tail}")
+}
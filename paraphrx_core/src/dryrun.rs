@@ -0,0 +1,76 @@
+// `--dry-run <dir>`: render model requests to files instead of sending them.
+//
+// Each request becomes one `<name>.txt` holding the prompt exactly as sent,
+// followed by the response schema, so template changes can be read and
+// diffed without spending API calls. Token counts are the usual ~4 chars per
+// token estimate, not the model's tokenizer.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+// Line between the prompt and the schema in a rendered request
+pub const SCHEMA_MARKER: &str = "--- schema ---";
+
+pub struct DryRun {
+    dir: PathBuf,
+    // prompt length in chars of every rendered request
+    sizes: Mutex<Vec<usize>>,
+}
+
+pub fn render(prompt: &str, schema: &Value) -> String {
+    // requests without a response schema are the prompt alone
+    if schema.is_null() {
+        return format!("{prompt}\n");
+    }
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_default();
+    format!("{prompt}\n\n{SCHEMA_MARKER}\n{schema}\n")
+}
+
+impl DryRun {
+    pub fn new(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create dry-run dir {}", dir.display()))?;
+        Ok(Self { dir: dir.to_path_buf(), sizes: Mutex::new(Vec::new()) })
+    }
+
+    pub fn write(&self, name: &str, prompt: &str, schema: &Value) -> Result<()> {
+        let path = self.dir.join(format!("{name}.txt"));
+        fs::write(&path, render(prompt, schema))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        self.sizes.lock().unwrap().push(prompt.chars().count());
+        Ok(())
+    }
+
+    // "12 requests in out/; prompt chars total 30120, mean 2510, max 3011; ≈ 7530 tokens (max 753)"
+    pub fn summary(&self) -> String {
+        let sizes = self.sizes.lock().unwrap();
+        let total: usize = sizes.iter().sum();
+        let max = sizes.iter().copied().max().unwrap_or(0);
+        let mean = if sizes.is_empty() { 0 } else { total / sizes.len() };
+        format!(
+            "{} requests in {}; prompt chars total {total}, mean {mean}, max {max}; \
+             ≈ {} tokens (max {})",
+            sizes.len(),
+            self.dir.display(),
+            total.div_ceil(4),
+            max.div_ceil(4)
+        )
+    }
+}
+
+static DRY_RUN: OnceLock<DryRun> = OnceLock::new();
+
+pub fn init(dir: &Path) -> Result<()> {
+    let _ = DRY_RUN.set(DryRun::new(dir)?);
+    Ok(())
+}
+
+// The process-wide dry run; `None` unless `init` was called
+pub fn global() -> Option<&'static DryRun> {
+    DRY_RUN.get()
+}
@@ -0,0 +1,129 @@
+// Gemini generateContent client.
+//
+// `query_gemini` sends one prompt and returns the JSON object the model
+// answered with. It goes through the response cache and the usage ledger
+// (both no-ops unless `init`-ed), so a cached answer costs nothing and is not
+// counted. `Query` carries the per-tool generation options; the request body
+// only holds the options that are set, so the body (and with it the cache
+// key) of a tool does not change when another tool needs a new option.
+
+use crate::{cache::{self, Cache}, usage};
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use serde_json::{json, Map as JsonMap, Value};
+use std::time::Duration;
+
+pub use reqwest::Client;
+
+pub const ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+const SAFETY_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    // `responseSchema`; without one the model is only asked for JSON
+    pub schema: Option<Value>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    // BLOCK_NONE on every harm category
+    pub no_safety_filters: bool,
+}
+
+impl Query {
+    pub fn with_schema(schema: Value) -> Self {
+        Self { schema: Some(schema), ..Default::default() }
+    }
+}
+
+pub fn build_client(timeout: Option<Duration>) -> Result<Client> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let mut builder = Client::builder().default_headers(headers);
+    if let Some(t) = timeout {
+        builder = builder.timeout(t);
+    }
+    Ok(builder.build()?)
+}
+
+pub fn request_body(prompt: &str, query: &Query) -> Value {
+    let mut config = JsonMap::new();
+    config.insert("responseMimeType".into(), json!("application/json"));
+    if let Some(schema) = &query.schema {
+        config.insert("responseSchema".into(), schema.clone());
+    }
+    if let Some(t) = query.temperature {
+        config.insert("temperature".into(), json!(t));
+    }
+    if let Some(p) = query.top_p {
+        config.insert("topP".into(), json!(p));
+    }
+    let mut body = json!({
+        "contents": [{"role": "user", "parts": [{"text": prompt}]}],
+        "generationConfig": config,
+    });
+    if query.no_safety_filters {
+        body["safetySettings"] = SAFETY_CATEGORIES
+            .iter()
+            .map(|c| json!({"category": c, "threshold": "BLOCK_NONE"}))
+            .collect();
+    }
+    body
+}
+
+// The answer text of the first candidate; some models put JSON answers in
+// `inlineData` instead of `text`
+pub fn response_text(resp: &Value) -> Option<&str> {
+    let part = &resp["candidates"][0]["content"]["parts"][0];
+    part["text"].as_str().or_else(|| part["inlineData"]["data"].as_str())
+}
+
+// The JSON object in an answer, tolerating code fences and chatter around it
+pub fn parse_response(s: &str) -> Result<JsonMap<String, Value>> {
+    let cleaned = s.trim().trim_start_matches("```json").trim_start_matches("```").trim_end_matches("```").trim();
+    if let Ok(v) = serde_json::from_str(cleaned) {
+        return Ok(v);
+    }
+    if let (Some(start), Some(end)) = (cleaned.find('{'), cleaned.rfind('}')) {
+        if let Ok(v) = serde_json::from_str(&cleaned[start..=end]) {
+            return Ok(v);
+        }
+    }
+    Err(anyhow!("could not parse a JSON object from response: {s}"))
+}
+
+pub async fn query_gemini(
+    client: &Client,
+    key: &str,
+    model: &str,
+    prompt: &str,
+    query: &Query,
+) -> Result<JsonMap<String, Value>> {
+    let url = format!("{ENDPOINT}/models/{model}:generateContent");
+    let body = request_body(prompt, query);
+    let cache = cache::global();
+    let cache_key = Cache::key(&url, model, &body);
+    let (resp_json, fresh) = match cache.and_then(|c| c.get(&cache_key)) {
+        Some(hit) => (hit, false),
+        None => {
+            let resp = client.post(format!("{url}?key={key}")).json(&body).send().await?;
+            if !resp.status().is_success() {
+                return Err(anyhow!("{} — {}", resp.status(), resp.text().await?));
+            }
+            let resp_json: Value = resp.json().await?;
+            usage::record_gemini(model, &resp_json);
+            (resp_json, true)
+        }
+    };
+    let text = response_text(&resp_json).ok_or_else(|| anyhow!("unexpected response structure"))?;
+    let obj = parse_response(text)?;
+    // only answers that parsed are worth replaying
+    if let (true, Some(c)) = (fresh, cache) {
+        c.put(&cache_key, &resp_json);
+    }
+    Ok(obj)
+}
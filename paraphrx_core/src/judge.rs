@@ -0,0 +1,58 @@
+// The 10-metric judge: the prompt that scores answers, and the response
+// schema that holds the judge to ten integers per scored key.
+
+use serde_json::{json, Map as JsonMap, Value};
+
+// Every key maps to exactly ten 0-10 scores, in metric order
+pub fn schema_for_keys(keys: &[String]) -> Value {
+    let mut props = JsonMap::new();
+    for k in keys {
+        props.insert(k.clone(), json!({"type":"array","items":{"type":"integer"},"minItems":10,"maxItems":10}));
+    }
+    json!({"type":"object","properties":props,"required":keys})
+}
+
+// Intro and the ten metrics, in score order
+const METRICS: &str = r#"You are an expert evaluator.
+
+For every answer below, assess it against **ten metrics**. Each metric must be scored on a 0-10 integer scale (higher is better).
+
+Metrics (use **exact** order):
+1. Task Fulfilment / Relevance - Does it respond to every part of the prompt? Did it wander off-topic or over-answer?
+2. Usefulness & Actionability - Does it translate abstract ideas into concrete advice, examples, or next steps?
+3. Factual Accuracy & Verifiabiliy - Are the statements factually correct (no hallucinations)? If it cites sources or internal steps, do those match the final claims?
+4. Efficiency / Depth & Completeness - Does it avoid unnecessary verbosity or excessive brevity? Does it cover the key angles, edge-cases, and typical follow-ups? Could the user act on it without having to ask “what about X?”
+5. Reasoning Quality / Transparency - Are the steps implicitly or explicitly sound? If uncertain, does it flag that uncertainty instead of bluffing?
+6. Tone & Likeability - Is the style friendly and respectful, matching the user’s vibe? Would you enjoy a longer conversation in this voice?
+7. Adaptation to Context - Does it use any relevant info the user has shared (location, preferences, prior messages) appropriately?
+8. Safety & Bias Avoidance - Does it steer clear of harmful or disallowed content? Does it acknowledge and mitigate possible bias?
+9. Structure & Formatting & UX Extras - Is the writing logically ordered and easy to skim? Are lists, code blocks, tables, or rich widgets used when, but only when they genuinely improve readability or utility?
+10. Creativity - Does the answer make clever, non-obvious connections that you wouldn’t get from a quick Google search? Or, does it remix ideas, metaphors, or examples in a fresh way rather than serving boilerplate?
+
+"#;
+
+const EVAL_MODEL: &str = r#"```python
+from typing import Dict, List
+from pydantic import BaseModel, conlist
+
+class EvalResult(BaseModel):
+    __root__: Dict[str, conlist(int, min_items=10, max_items=10)]
+```
+
+"#;
+
+// `section` holds one `### <key>` block (instruction, then answer) per key
+pub fn build_eval_prompt(section: &str) -> String {
+    format!(
+        "{METRICS}Return **only** JSON conforming to this Pydantic model (nothing else, no code fences):\n\n{EVAL_MODEL}Begin data to evaluate:\n\n{section}\n"
+    )
+}
+
+// The score_results wording of the answer format. Kept apart so its scores
+// stay comparable with earlier runs and its cached responses keep matching.
+pub fn build_finetune_eval_prompt(section: &str) -> String {
+    format!(
+        "{METRICS}Return *only* valid JSON (no markdown, no code fences) where each key maps to an array of ten integers 0-10.\n\
+         The returned JSON must confirm to this Pydantic model:\n\n{EVAL_MODEL}Begin data to evaluate:\n\n{section}\n"
+    )
+}
@@ -0,0 +1,19 @@
+// Shared core of the judge binaries (c_assess_inf, f_finetune): the record
+// shape, the Gemini client, the 10-metric judge prompt and the run-wide
+// modules every judge wires up the same way (rephras reuses those too)
+
+pub mod cache;
+pub mod contracts;
+pub mod dryrun;
+pub mod gemini;
+pub mod judge;
+pub mod logger;
+pub mod records;
+pub mod scale;
+pub mod scrub;
+pub mod usage;
+
+pub use gemini::{build_client, query_gemini, Query};
+pub use judge::{build_eval_prompt, build_finetune_eval_prompt, schema_for_keys};
+pub use logger::Logger;
+pub use records::{load_records, read_records, Record};
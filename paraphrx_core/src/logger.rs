@@ -0,0 +1,44 @@
+// Tiny run logger: one `[YYYY-mm-dd HH:MM:SS] msg` line per call, flushed
+// right away so a crashed run still leaves its log behind.
+
+use anyhow::Result;
+use chrono::Local;
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+pub struct Logger {
+    writer: BufWriter<fs::File>,
+}
+
+impl Logger {
+    // Starts `p` afresh
+    pub fn new<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let file = fs::OpenOptions::new().create(true).truncate(true).write(true).open(p)?;
+        Ok(Self { writer: BufWriter::new(file) })
+    }
+
+    // Appends to `p`, for resumable runs that keep one log across restarts
+    pub fn append<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let file = fs::OpenOptions::new().create(true).append(true).open(p)?;
+        Ok(Self { writer: BufWriter::new(file) })
+    }
+
+    pub fn log(&mut self, msg: &str) {
+        let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
+        let _ = writeln!(self.writer, "[{ts}] {msg}");
+        let _ = self.writer.flush();
+    }
+}
+
+// A warning from the shared modules: into the `log` backend when the tool
+// installed one (rephras logs to a file), else on stderr
+pub(crate) fn warn(msg: &str) {
+    if log::max_level() >= log::LevelFilter::Warn {
+        log::warn!("{msg}");
+    } else {
+        eprintln!("{msg}");
+    }
+}
@@ -0,0 +1,93 @@
+// The record shape every judge reads. Paraphrase files and answer files are
+// both arrays of objects joined on `prompt_count`; the original instruction
+// goes by `instruction` or `instruction_original`, and every other key
+// (`instruct_*` variants, `prompt_id`, MMLU `choices`, ...) is kept in `extra`.
+
+use crate::logger::Logger;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map as JsonMap, Value};
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Record {
+    // a number, or a numeric string in older exports
+    #[serde(deserialize_with = "de_prompt_count")]
+    pub prompt_count: u32,
+
+    #[serde(alias = "instruction", alias = "instruction_original", default)]
+    pub instruction_original: String,
+
+    // the answer text for generation sets, the gold choice index for MMLU
+    #[serde(default)]
+    pub output: Option<Value>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, Value>,
+}
+
+impl Record {
+    // `output` when it is text
+    pub fn output_text(&self) -> Option<&str> {
+        self.output.as_ref().and_then(Value::as_str)
+    }
+}
+
+fn de_prompt_count<'de, D: Deserializer<'de>>(de: D) -> Result<u32, D::Error> {
+    struct Visitor;
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = u32;
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("integer or string for prompt_count")
+        }
+        fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<u32, E> {
+            u32::try_from(v).map_err(|_| E::custom(format!("invalid prompt_count {v}")))
+        }
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<u32, E> {
+            v.parse().map_err(|_| E::custom(format!("invalid prompt_count {v}")))
+        }
+    }
+    de.deserialize_any(Visitor)
+}
+
+// One record from its JSON object. A `paraphrases: [{instruct_type,
+// paraphrase}]` list (the finetune exports) is unfolded into `extra` as
+// `<instruct_type>: <paraphrase>`, the shape of the generator output.
+pub fn record_from_value(raw: &Value) -> Result<Record> {
+    let mut rec = Record::deserialize(raw)?;
+    if let Some(pars) = raw.get("paraphrases").and_then(Value::as_array) {
+        for p in pars {
+            if let (Some(t), Some(txt)) = (
+                p.get("instruct_type").and_then(Value::as_str),
+                p.get("paraphrase").and_then(Value::as_str),
+            ) {
+                rec.extra.insert(t.to_string(), Value::String(txt.to_string()));
+            }
+        }
+    }
+    Ok(rec)
+}
+
+// Every record of a JSON array file, in file order
+pub fn load_records<P: AsRef<Path>>(path: P) -> Result<Vec<Record>> {
+    let path = path.as_ref();
+    let raw = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let items: Vec<Value> = serde_json::from_str(&raw).with_context(|| format!("{} is not a JSON array", path.display()))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| record_from_value(item).with_context(|| format!("{} record {i}", path.display())))
+        .collect()
+}
+
+// Fault-tolerant loader keyed by `prompt_count`: a file that cannot be read
+// is logged and comes back empty, so the run skips it instead of stopping
+pub fn read_records(path: &Path, logger: &mut Logger) -> HashMap<String, Record> {
+    match load_records(path) {
+        Ok(records) => records.into_iter().map(|r| (r.prompt_count.to_string(), r)).collect(),
+        Err(e) => {
+            logger.log(&format!("[fatal-but-skipped] could not parse {}: {e:#}", path.display()));
+            HashMap::new()
+        }
+    }
+}
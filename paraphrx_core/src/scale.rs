@@ -0,0 +1,15 @@
+// Keys of the graded scales (`[scales.*]` in a_data/paraphrase_taxonomy.toml):
+// level `n` of dimension `dim` is stored as `instruct_<dim>_L<n>`. The
+// generator writes these keys and summarise_scores reads them back, so both
+// go through this one pair of functions.
+
+pub fn scale_key(dim: &str, level: u32) -> String {
+    format!("instruct_{dim}_L{level}")
+}
+
+// `instruct_politeness_L3` -> ("politeness", 3)
+pub fn parse_scale_key(key: &str) -> Option<(&str, u32)> {
+    let (dim, level) = key.strip_prefix("instruct_")?.rsplit_once("_L")?;
+    let level = level.parse().ok().filter(|l| *l > 0)?;
+    (!dim.is_empty()).then_some((dim, level))
+}
@@ -0,0 +1,283 @@
+// `--scrub`: mask PII and secrets before a prompt leaves the machine.
+//
+// Every pattern of the patterns file (a_data/scrub_patterns.toml) is applied
+// to the rendered prompt in file order; a match becomes `[<NAME>_<n>]`, and
+// the same text always gets the same placeholder, so a value that appears in
+// several prompts (or an answer that repeats an address from its instruction)
+// stays one value to the model. The placeholder -> original mapping is kept
+// in a map file next to the output; `restore` reverses the masking, which is
+// how model output is put back. The file holds the very data that was kept
+// back, so it stays local.
+//
+// The map file also counts the redacted items per set and pattern; a prompt
+// sent again (retries, candidates) is only counted once. The judges use the
+// process-wide scrubber: `init` it in `main`, then `apply` masks each prompt
+// (and passes it through unchanged without `init`). rephras holds its own
+// `Scrubber` per set instead.
+
+use anyhow::{bail, Context, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+pub const DEFAULT_SCRUB_PATTERNS: &str = "a_data/scrub_patterns.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternsFile {
+    #[serde(default)]
+    allow: Vec<String>,
+    patterns: Vec<PatternSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternSpec {
+    name: String,
+    pattern: String,
+}
+
+struct Pattern {
+    name: String,
+    // `EMAIL` for `email`
+    label: String,
+    re: Regex,
+}
+
+// Contents of the map file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScrubMap {
+    #[serde(default)]
+    patterns: Vec<String>,
+    // set -> pattern -> redacted items
+    #[serde(default)]
+    sets: BTreeMap<String, BTreeMap<String, u64>>,
+    // placeholder -> original text
+    #[serde(default)]
+    map: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+struct State {
+    file: ScrubMap,
+    // original text -> placeholder
+    by_text: HashMap<String, String>,
+    // label -> highest number handed out
+    next: HashMap<String, usize>,
+    // (set, prompt) hashes already counted
+    counted: HashSet<[u8; 32]>,
+}
+
+impl State {
+    fn placeholder(&mut self, label: &str, text: &str) -> String {
+        if let Some(p) = self.by_text.get(text) {
+            return p.clone();
+        }
+        let n = self.next.entry(label.to_string()).or_default();
+        *n += 1;
+        let p = format!("[{label}_{n}]");
+        self.by_text.insert(text.to_string(), p.clone());
+        self.file.map.insert(p.clone(), text.to_string());
+        p
+    }
+}
+
+pub struct Scrubber {
+    patterns: Vec<Pattern>,
+    // lower-cased
+    allow: Vec<String>,
+    placeholder: Regex,
+    map_path: PathBuf,
+    state: Mutex<State>,
+}
+
+impl Scrubber {
+    // Patterns from `patterns`. The placeholders of an existing map file at
+    // `map_path` are reused (same prompts, same cache keys); its counts only
+    // with `keep_counts`, for a resumed run
+    pub fn new(patterns: &Path, map_path: &Path, keep_counts: bool) -> Result<Self> {
+        let raw = fs::read_to_string(patterns)
+            .with_context(|| format!("failed to read scrub patterns {}", patterns.display()))?;
+        let mut scrubber = Self::from_toml_str(&raw, map_path)
+            .with_context(|| format!("invalid scrub patterns {}", patterns.display()))?;
+
+        if let Ok(raw) = fs::read_to_string(map_path) {
+            let file: ScrubMap = serde_json::from_str(&raw)
+                .with_context(|| format!("{} is not a scrub map", map_path.display()))?;
+            let state = scrubber.state.get_mut().unwrap();
+            for (p, text) in &file.map {
+                state.by_text.insert(text.clone(), p.clone());
+                if let Some((label, n)) = split_placeholder(p) {
+                    let next = state.next.entry(label.to_string()).or_default();
+                    *next = (*next).max(n);
+                }
+            }
+            state.file.map = file.map;
+            if keep_counts {
+                state.file.sets = file.sets;
+            }
+        }
+        Ok(scrubber)
+    }
+
+    pub fn from_toml_str(raw: &str, map_path: &Path) -> Result<Self> {
+        let file: PatternsFile = toml::from_str(raw)?;
+        if file.patterns.is_empty() {
+            bail!("no [[patterns]]");
+        }
+        let mut patterns = Vec::new();
+        for spec in file.patterns {
+            let re = Regex::new(&spec.pattern)
+                .with_context(|| format!("pattern {}: bad regex", spec.name))?;
+            let label = spec.name.to_uppercase();
+            patterns.push(Pattern { name: spec.name, label, re });
+        }
+        let state = State {
+            file: ScrubMap { patterns: patterns.iter().map(|p| p.name.clone()).collect(), ..Default::default() },
+            ..Default::default()
+        };
+        Ok(Self {
+            patterns,
+            allow: file.allow.iter().map(|a| a.to_lowercase()).collect(),
+            placeholder: Regex::new(r"\[[A-Z][A-Z0-9_]*_[0-9]+\]").expect("placeholder regex"),
+            map_path: map_path.to_path_buf(),
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn map_path(&self) -> &Path {
+        &self.map_path
+    }
+
+    // `text` with every match masked; the items count towards `set`
+    pub fn scrub(&self, set: &str, text: &str) -> String {
+        let mut state = self.state.lock().unwrap();
+        let key: [u8; 32] = Sha256::new().chain_update(set).chain_update([0]).chain_update(text).finalize().into();
+        let first_time = state.counted.insert(key);
+
+        let mut out = text.to_string();
+        for pattern in &self.patterns {
+            let mut n = 0u64;
+            out = pattern
+                .re
+                .replace_all(&out, |caps: &Captures| {
+                    let m = &caps[0];
+                    if self.placeholder.is_match(m) || self.allowed(m) {
+                        return m.to_string();
+                    }
+                    n += 1;
+                    state.placeholder(&pattern.label, m)
+                })
+                .into_owned();
+            if first_time && n > 0 {
+                *state.file.sets.entry(set.to_string()).or_default().entry(pattern.name.clone()).or_default() += n;
+            }
+        }
+        out
+    }
+
+    // Placeholders in `text` put back; unknown ones are left as they are
+    pub fn restore(&self, text: &str) -> String {
+        let state = self.state.lock().unwrap();
+        self.placeholder
+            .replace_all(text, |caps: &Captures| state.file.map.get(&caps[0]).cloned().unwrap_or_else(|| caps[0].to_string()))
+            .into_owned()
+    }
+
+    // Every string inside `value` restored
+    pub fn restore_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.restore(&s)),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.restore_value(v)).collect()),
+            Value::Object(obj) => Value::Object(obj.into_iter().map(|(k, v)| (k, self.restore_value(v))).collect()),
+            other => other,
+        }
+    }
+
+    fn allowed(&self, m: &str) -> bool {
+        let m = m.to_lowercase();
+        self.allow.iter().any(|a| m.contains(a.as_str()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        fs::write(&self.map_path, serde_json::to_string_pretty(&state.file)?)
+            .with_context(|| format!("failed to write {}", self.map_path.display()))
+    }
+
+    // One line per set: "style: 14 items redacted (email 3, url 11)"
+    pub fn summary(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .file
+            .sets
+            .iter()
+            .map(|(set, counts)| {
+                let total: u64 = counts.values().sum();
+                let parts: Vec<String> = counts.iter().map(|(name, n)| format!("{name} {n}")).collect();
+                format!("{set}: {total} items redacted ({})", parts.join(", "))
+            })
+            .collect()
+    }
+}
+
+// `[EMAIL_12]` -> ("EMAIL", 12)
+fn split_placeholder(p: &str) -> Option<(&str, usize)> {
+    let inner = p.strip_prefix('[')?.strip_suffix(']')?;
+    let (label, n) = inner.rsplit_once('_')?;
+    Some((label, n.parse().ok()?))
+}
+
+// `<output>.scrub.json`
+pub fn map_path_for(output: &Path) -> PathBuf {
+    output.with_extension("scrub.json")
+}
+
+// The scrubber and the set its items count towards by default
+static SCRUBBER: OnceLock<(Scrubber, String)> = OnceLock::new();
+
+// The map file starts from the placeholders of an earlier run, not its counts
+pub fn init(patterns: &Path, map_path: &Path, set: &str) -> Result<()> {
+    let _ = SCRUBBER.set((Scrubber::new(patterns, map_path, false)?, set.to_string()));
+    Ok(())
+}
+
+pub fn global() -> Option<&'static Scrubber> {
+    SCRUBBER.get().map(|(s, _)| s)
+}
+
+// `prompt` masked when `--scrub` is on, else unchanged
+pub fn apply(prompt: String) -> String {
+    match SCRUBBER.get() {
+        Some((s, set)) => s.scrub(set, &prompt),
+        None => prompt,
+    }
+}
+
+// `apply` for tools that judge several sets in one run
+pub fn apply_to(set: &str, prompt: String) -> String {
+    match global() {
+        Some(s) => s.scrub(set, &prompt),
+        None => prompt,
+    }
+}
+
+// Write the map file; the redaction counts per set (plus where the map went)
+// for the log, nothing without `init`
+pub fn finish() -> Result<Vec<String>> {
+    let Some(s) = global() else { return Ok(Vec::new()) };
+    s.save()?;
+    let mut lines = s.summary();
+    if lines.is_empty() {
+        lines.push("nothing redacted".to_string());
+    }
+    lines.push(format!("map written to {}", s.map_path().display()));
+    Ok(lines)
+}
@@ -0,0 +1,157 @@
+// Token usage ledger.
+//
+// Every generation response carries its token counts (`usageMetadata` from
+// Gemini, `usage` from OpenAI-compatible servers). Each call is appended as
+// one JSON line to a per-run ledger `usage/<timestamp>_<tool>_<run>.jsonl`,
+// which `cargo cost_report` totals by model, tool, dataset and set. The ledger
+// is process-wide: `init` it once in `main` and call `record` (or
+// `record_gemini`) wherever a response comes back; without `init` nothing is
+// written.
+
+use crate::logger;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const DEFAULT_USAGE_DIR: &str = "usage";
+
+// Dataset names recognised in paths by `guess_dataset`
+const DATASETS: &[&str] = &["alpaca", "gsm8k", "mmlu"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub candidates_tokens: u64,
+    // reasoning tokens of thinking models, billed as output
+    #[serde(default)]
+    pub thoughts_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    // `usageMetadata` of a generateContent response
+    pub fn from_gemini(resp: &Value) -> Option<Self> {
+        let m = resp.get("usageMetadata")?;
+        let n = |k: &str| m[k].as_u64().unwrap_or(0);
+        Some(Self {
+            prompt_tokens: n("promptTokenCount"),
+            candidates_tokens: n("candidatesTokenCount"),
+            thoughts_tokens: n("thoughtsTokenCount"),
+            total_tokens: n("totalTokenCount"),
+        })
+    }
+
+    // `usage` of a chat/completions response; reasoning tokens are already
+    // part of `completion_tokens` there
+    pub fn from_openai(resp: &Value) -> Option<Self> {
+        let u = resp.get("usage")?;
+        let n = |k: &str| u[k].as_u64().unwrap_or(0);
+        Some(Self {
+            prompt_tokens: n("prompt_tokens"),
+            candidates_tokens: n("completion_tokens"),
+            thoughts_tokens: 0,
+            total_tokens: n("total_tokens"),
+        })
+    }
+}
+
+// One ledger line = one API call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub ts: String,
+    pub tool: String,
+    pub model: String,
+    pub dataset: Option<String>,
+    pub version_set: Option<String>,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+struct Ledger {
+    tool: String,
+    dataset: Option<String>,
+    version_set: Option<String>,
+    writer: BufWriter<fs::File>,
+}
+
+static LEDGER: Mutex<Option<Ledger>> = Mutex::new(None);
+
+// Start this run's ledger in `dir` and return its path
+pub fn init(
+    dir: &Path,
+    tool: &str,
+    run: &str,
+    dataset: Option<&str>,
+    version_set: Option<&str>,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let ts = Local::now().format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("{ts}_{tool}_{run}.jsonl"));
+    let file = fs::File::create(&path)
+        .with_context(|| format!("failed to create usage ledger {}", path.display()))?;
+
+    *LEDGER.lock().unwrap() = Some(Ledger {
+        tool: tool.to_string(),
+        dataset: dataset.map(String::from),
+        version_set: version_set.map(String::from),
+        writer: BufWriter::new(file),
+    });
+    Ok(path)
+}
+
+// Tools that walk several sets in one run switch the label between them
+pub fn set_version_set(name: &str) {
+    if let Some(ledger) = LEDGER.lock().unwrap().as_mut() {
+        ledger.version_set = Some(name.to_string());
+    }
+}
+
+// Append the usage of one generateContent response, if it reported any
+pub fn record_gemini(model: &str, resp: &Value) {
+    if let Some(usage) = Usage::from_gemini(resp) {
+        record(model, usage);
+    }
+}
+
+pub fn record(model: &str, usage: Usage) {
+    let mut guard = LEDGER.lock().unwrap();
+    let Some(ledger) = guard.as_mut() else {
+        return;
+    };
+    let entry = Entry {
+        ts: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        tool: ledger.tool.clone(),
+        model: model.to_string(),
+        dataset: ledger.dataset.clone(),
+        version_set: ledger.version_set.clone(),
+        usage,
+    };
+    // flush per line; a broken ledger must not stop the run
+    let written = serde_json::to_writer(&mut ledger.writer, &entry)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(ledger.writer.write_all(b"\n")?))
+        .and_then(|_| Ok(ledger.writer.flush()?));
+    if let Err(e) = written {
+        logger::warn(&format!("usage ledger: {e:#}"));
+    }
+}
+
+// First path component naming a known dataset (`alpaca_prxed` -> alpaca)
+pub fn guess_dataset(path: &Path) -> Option<String> {
+    path.components().find_map(|c| {
+        let name = c.as_os_str().to_string_lossy().to_lowercase();
+        DATASETS.iter().find(|d| name.starts_with(*d)).map(|d| d.to_string())
+    })
+}
+
+// Set files are named after the set (`answers_slice_500/style.json`)
+pub fn guess_version_set(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().into_owned())
+}
@@ -0,0 +1,194 @@
+// The shared judge API: record loading, the judge prompt and schema, the
+// Gemini request body and response parsing. Nothing here talks to the network.
+//
+//   cargo test -p paraphrx-core
+
+use paraphrx_core::cache::{Cache, CacheMode};
+use paraphrx_core::gemini::{parse_response, request_body, response_text};
+use paraphrx_core::scale::{parse_scale_key, scale_key};
+use paraphrx_core::{build_eval_prompt, build_finetune_eval_prompt, load_records, read_records, schema_for_keys, Logger, Query};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("paraphrx_core_{}_{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, value: &Value) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, serde_json::to_string_pretty(value).unwrap()).unwrap();
+    path
+}
+
+#[test]
+fn records_accept_every_known_shape() {
+    let dir = scratch("shapes");
+    let path = write(
+        &dir,
+        "records.json",
+        &json!([
+            {"prompt_count": 1, "instruction_original": "Name a fruit.", "output": "Apple", "instruct_polite": "Please name a fruit."},
+            {"prompt_count": "2", "instruction": "Pick one.", "output": 3, "choices": ["a", "b", "c", "d"]},
+            {"prompt_count": 3, "paraphrases": [{"instruct_type": "instruct_terse", "paraphrase": "Fruit?"}]},
+        ]),
+    );
+    let records = load_records(&path).unwrap();
+    assert_eq!(records.len(), 3);
+
+    assert_eq!(records[0].instruction_original, "Name a fruit.");
+    assert_eq!(records[0].output_text(), Some("Apple"));
+    assert_eq!(records[0].extra["instruct_polite"], "Please name a fruit.");
+
+    // string prompt_count, `instruction` alias, MMLU gold index
+    assert_eq!(records[1].prompt_count, 2);
+    assert_eq!(records[1].instruction_original, "Pick one.");
+    assert_eq!(records[1].output, Some(json!(3)));
+    assert_eq!(records[1].output_text(), None);
+
+    // `paraphrases` unfolded into `extra`
+    assert_eq!(records[2].instruction_original, "");
+    assert_eq!(records[2].extra["instruct_terse"], "Fruit?");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn read_records_keys_by_prompt_count_and_skips_bad_files() {
+    let dir = scratch("read");
+    let mut logger = Logger::new(dir.join("run.logs")).unwrap();
+
+    let good = write(&dir, "good.json", &json!([{"prompt_count": 7, "instruction": "x"}, {"prompt_count": 9, "instruction": "y"}]));
+    let map = read_records(&good, &mut logger);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["9"].instruction_original, "y");
+
+    let bad = write(&dir, "bad.json", &json!([{"prompt_count": "nine"}]));
+    assert!(read_records(&bad, &mut logger).is_empty());
+    assert!(read_records(&dir.join("missing.json"), &mut logger).is_empty());
+
+    let log = fs::read_to_string(dir.join("run.logs")).unwrap();
+    assert_eq!(log.matches("[fatal-but-skipped]").count(), 2);
+    assert!(log.contains("invalid prompt_count nine"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn logger_new_truncates_and_append_keeps() {
+    let dir = scratch("logger");
+    let path = dir.join("run.logs");
+    Logger::new(&path).unwrap().log("first");
+    Logger::append(&path).unwrap().log("second");
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    Logger::new(&path).unwrap().log("third");
+    let log = fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.ends_with("] third\n"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn judge_prompt_and_schema() {
+    let section = "### instruct_polite\n[Instruction]\nx\n\n[Answer]\ny\n\n";
+    let prompt = build_eval_prompt(section);
+    assert!(prompt.starts_with("You are an expert evaluator."));
+    assert!(prompt.contains("\n10. Creativity - "));
+    assert!(prompt.ends_with(&format!("Begin data to evaluate:\n\n{section}\n")));
+    assert!(prompt.contains("\nReturn **only** JSON conforming to this Pydantic model (nothing else, no code fences):\n\n```python\n"));
+
+    // score_results keeps its own answer-format wording
+    let finetune = build_finetune_eval_prompt(section);
+    assert!(finetune.contains(
+        "\nReturn *only* valid JSON (no markdown, no code fences) where each key maps to an array of ten integers 0-10.\n\
+         The returned JSON must confirm to this Pydantic model:\n\n```python\n"
+    ));
+    assert_eq!(finetune.split("Return ").next(), prompt.split("Return ").next());
+    assert!(finetune.ends_with(&format!("Begin data to evaluate:\n\n{section}\n")));
+
+    let keys = vec!["instruction_original".to_string(), "instruct_polite".to_string()];
+    let schema = schema_for_keys(&keys);
+    assert_eq!(schema["required"], json!(keys));
+    assert_eq!(schema["properties"]["instruct_polite"]["minItems"], 10);
+    assert_eq!(schema["properties"]["instruct_polite"]["maxItems"], 10);
+}
+
+#[test]
+fn request_body_only_carries_the_options_set() {
+    let plain = request_body("hi", &Query::default());
+    assert_eq!(
+        plain,
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "hi"}]}],
+            "generationConfig": {"responseMimeType": "application/json"},
+        })
+    );
+
+    let schema = json!({"type": "object"});
+    let with_schema = request_body("hi", &Query::with_schema(schema.clone()));
+    assert_eq!(with_schema["generationConfig"]["responseSchema"], schema);
+    assert!(with_schema.get("safetySettings").is_none());
+
+    let query = Query { temperature: Some(0.0), top_p: Some(0.95), no_safety_filters: true, ..Default::default() };
+    let tuned = request_body("hi", &query);
+    assert_eq!(tuned["generationConfig"]["temperature"], 0.0);
+    assert_eq!(tuned["generationConfig"]["topP"], 0.95);
+    let safety = tuned["safetySettings"].as_array().unwrap();
+    assert_eq!(safety.len(), 4);
+    assert!(safety.iter().all(|s| s["threshold"] == "BLOCK_NONE"));
+}
+
+#[test]
+fn responses_parse_from_text_or_inline_data() {
+    let text = json!({"candidates": [{"content": {"parts": [{"text": "{\"a\": [1]}"}]}}]});
+    assert_eq!(response_text(&text), Some("{\"a\": [1]}"));
+    let inline = json!({"candidates": [{"content": {"parts": [{"inlineData": {"data": "{}"}}]}}]});
+    assert_eq!(response_text(&inline), Some("{}"));
+    assert_eq!(response_text(&json!({"candidates": []})), None);
+
+    assert_eq!(parse_response("{\"a\": 1}").unwrap()["a"], 1);
+    assert_eq!(parse_response("```json\n{\"a\": 2}\n```").unwrap()["a"], 2);
+    assert_eq!(parse_response("Sure! {\"a\": 3} Hope that helps.").unwrap()["a"], 3);
+    assert!(parse_response("no json here").is_err());
+    assert!(parse_response("[1, 2]").is_err());
+}
+
+#[test]
+fn concurrent_puts_of_one_key_never_mix() {
+    let dir = scratch("cache");
+    let cache = Cache::new(&dir, CacheMode::Read).unwrap();
+    let key = Cache::key("endpoint", "model", &json!({"prompt": "same"}));
+    let big = "x".repeat(256 * 1024);
+    std::thread::scope(|s| {
+        for t in 0..8 {
+            let (cache, key, big) = (&cache, &key, &big);
+            s.spawn(move || {
+                for i in 0..20 {
+                    cache.put(key, &json!({"writer": t, "round": i, "pad": big}));
+                }
+            });
+        }
+    });
+    assert_eq!(cache.stats().stored, 160);
+    let hit = cache.get(&key).expect("a complete response");
+    assert_eq!(hit["pad"].as_str().unwrap().len(), big.len());
+
+    // nothing but the stored response is left behind
+    let files: Vec<_> = fs::read_dir(dir.join(&key[..2])).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+    assert_eq!(files, [format!("{key}.json")]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn scale_keys_round_trip() {
+    assert_eq!(scale_key("politeness", 3), "instruct_politeness_L3");
+    assert_eq!(parse_scale_key(&scale_key("politeness", 3)), Some(("politeness", 3)));
+    // the last `_L` splits, so dimensions may hold one too
+    assert_eq!(parse_scale_key("instruct_formal_Lx_L12"), Some(("formal_Lx", 12)));
+    for key in ["instruct_politeness", "instruct_politeness_L0", "instruct__L2", "politeness_L2", "instruct_x_Lthree"] {
+        assert_eq!(parse_scale_key(key), None, "{key}");
+    }
+}